@@ -0,0 +1,133 @@
+/// statistic of repeated y values at the same x.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Aggregate {
+    Mean,
+    Median,
+}
+/// range drawn around the aggregated value.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Spread {
+    Std,
+    Sem,
+    Ci95,
+    MinMax,
+}
+
+impl Aggregate {
+    pub fn new(arg: &str) -> Option<Aggregate> {
+        match arg {
+            "mean" => Some(Aggregate::Mean),
+            "median" => Some(Aggregate::Median),
+            _ => None,
+        }
+    }
+    fn apply(&self, ys: &[f64]) -> f64 {
+        match *self {
+            Aggregate::Mean => mean(ys),
+            Aggregate::Median => {
+                let mut sorted = ys.to_vec();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+                let n = sorted.len();
+                if n % 2 == 1 {
+                    sorted[n / 2]
+                } else {
+                    (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+                }
+            }
+        }
+    }
+}
+impl Spread {
+    pub fn new(arg: &str) -> Option<Spread> {
+        match arg {
+            "std" => Some(Spread::Std),
+            "sem" => Some(Spread::Sem),
+            "ci95" => Some(Spread::Ci95),
+            "minmax" => Some(Spread::MinMax),
+            _ => None,
+        }
+    }
+    /// (low, high) around `center`.
+    fn apply(&self, ys: &[f64], center: f64) -> (f64, f64) {
+        let n = ys.len() as f64;
+        let half_width = match *self {
+            Spread::Std => standard_deviation(ys),
+            Spread::Sem => standard_deviation(ys) / n.sqrt(),
+            Spread::Ci95 => t_quantile(ys.len()) * standard_deviation(ys) / n.sqrt(),
+            Spread::MinMax => {
+                return (ys.iter().cloned().fold(f64::INFINITY, f64::min),
+                        ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max));
+            }
+        };
+        (center - half_width, center + half_width)
+    }
+}
+
+/// group values by x, and compute columns (x, center[, low, high]) in ascending order of x.
+pub fn aggregate(xs: &[f64],
+                 ys: &[f64],
+                 statistic: &Aggregate,
+                 spread: Option<&Spread>)
+                 -> Vec<Vec<f64>> {
+    let mut order = (0..xs.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| xs[a].partial_cmp(&xs[b]).unwrap_or(::std::cmp::Ordering::Equal));
+    let mut table = vec![Vec::new(); if spread.is_some() { 4 } else { 2 }];
+    let mut start = 0;
+    while start < order.len() {
+        let x = xs[order[start]];
+        let end = order[start..]
+            .iter()
+            .position(|&i| xs[i] != x)
+            .map_or(order.len(), |p| start + p);
+        let group = order[start..end].iter().map(|&i| ys[i]).collect::<Vec<_>>();
+        let center = statistic.apply(&group);
+        table[0].push(x);
+        table[1].push(center);
+        if let Some(spread) = spread {
+            let (low, high) = spread.apply(&group, center);
+            table[2].push(low);
+            table[3].push(high);
+        }
+        start = end;
+    }
+    table
+}
+
+fn mean(ys: &[f64]) -> f64 {
+    ys.iter().sum::<f64>() / ys.len() as f64
+}
+/// sample standard deviation. 0 for a single value.
+fn standard_deviation(ys: &[f64]) -> f64 {
+    if ys.len() < 2 {
+        return 0.0;
+    }
+    let m = mean(ys);
+    (ys.iter().map(|y| (y - m) * (y - m)).sum::<f64>() / (ys.len() - 1) as f64).sqrt()
+}
+/// two-sided 95% quantile of Student's t distribution for `n` samples.
+fn t_quantile(n: usize) -> f64 {
+    const T: [f64; 30] = [12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+                          2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+                          2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042];
+    match n {
+        0 | 1 => 0.0,
+        _ if n - 1 <= T.len() => T[n - 2],
+        _ => 1.960,
+    }
+}
+
+#[test]
+fn aggregate_test() {
+    let xs = vec![2.0, 1.0, 1.0, 2.0, 1.0];
+    let ys = vec![4.0, 1.0, 3.0, 6.0, 8.0];
+    assert_eq!(aggregate(&xs, &ys, &Aggregate::Mean, None),
+               vec![vec![1.0, 2.0], vec![4.0, 5.0]]);
+    assert_eq!(aggregate(&xs, &ys, &Aggregate::Median, Some(&Spread::MinMax)),
+               vec![vec![1.0, 2.0], vec![3.0, 5.0], vec![1.0, 4.0], vec![8.0, 6.0]]);
+    let table = aggregate(&xs, &ys, &Aggregate::Mean, Some(&Spread::Std));
+    assert!((table[3][1] - (5.0 + 2.0f64.sqrt())).abs() < 1e-9);
+    let table = aggregate(&[0.0, 0.0], &[1.0, 3.0], &Aggregate::Mean, Some(&Spread::Ci95));
+    assert!((table[2][0] - (2.0 - 12.706)).abs() < 1e-9);
+    assert_eq!(Aggregate::new("mode"), None);
+    assert_eq!(Spread::new("sem"), Some(Spread::Sem));
+}
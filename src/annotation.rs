@@ -0,0 +1,202 @@
+use expr;
+use std::io;
+use std::io::prelude::*;
+use timefmt::TimeFormat;
+
+/// what is drawn by an annotation, in coordinates of x and y axes.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Shape {
+    Label(String, (f64, f64)),
+    Arrow((f64, f64), (f64, f64)),
+    /// horizontal line across the plot at y.
+    HLine(f64),
+    /// vertical line across the plot at x.
+    VLine(f64),
+    /// shaded rectangle across the plot from x1 to x2.
+    Span(f64, f64),
+}
+/// a shape with color and dash pattern as given by user, which are checked by the caller.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Annotation {
+    pub shape: Shape,
+    pub color: Option<String>,
+    pub dash: Option<String>,
+}
+
+/// error in reading annotations from rows.
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// (line, row) a row which is not an annotation.
+    Invalid(usize, String),
+}
+
+pub const KINDS: [&str; 5] = ["label", "arrow", "hline", "vline", "span"];
+
+impl Annotation {
+    /// parse `TEXT at X,Y` (label), `X1,Y1:X2,Y2` (arrow), `Y` (hline), `X` (vline) or
+    /// `X1:X2` (span), followed by `,COLOR[,DASH]`. x values are times if `x_time` is given.
+    pub fn new(kind: &str, arg: &str, x_time: Option<&TimeFormat>) -> Option<Annotation> {
+        let x = |v: &str| x_value(v, x_time);
+        let y = |v: &str| v.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+        let (shape, style) = match kind {
+            "label" => {
+                let (text, at) = arg.rsplit_once(" at ")?;
+                let fields = expr::split_top_level(at, ',');
+                (Shape::Label(text.trim().to_string(), (x(&fields[0])?, y(fields.get(1)?)?)),
+                 fields[2..].to_vec())
+            }
+            "arrow" => {
+                let fields = expr::split_top_level(arg, ',');
+                let (y1, x2) = fields.get(1)?.split_once(':')?;
+                (Shape::Arrow((x(&fields[0])?, y(y1)?), (x(x2)?, y(fields.get(2)?)?)),
+                 fields[3..].to_vec())
+            }
+            "hline" | "vline" => {
+                let fields = expr::split_top_level(arg, ',');
+                let shape = if kind == "hline" {
+                    Shape::HLine(y(&fields[0])?)
+                } else {
+                    Shape::VLine(x(&fields[0])?)
+                };
+                (shape, fields[1..].to_vec())
+            }
+            "span" => {
+                let fields = expr::split_top_level(arg, ',');
+                // times may contain ':', so try each ':' until both ends are x values
+                let (x1, x2) = fields[0]
+                    .match_indices(':')
+                    .find_map(|(i, _)| Some((x(&fields[0][..i])?, x(&fields[0][i + 1..])?)))?;
+                (Shape::Span(x1, x2), fields[1..].to_vec())
+            }
+            _ => return None,
+        };
+        if style.len() > 2 {
+            return None;
+        }
+        let style = |i: usize| style.get(i).map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        Some(Annotation {
+            shape,
+            color: style(0),
+            dash: style(1),
+        })
+    }
+    /// gnuplot's `set` command with `color` and `dash` as specifiers. (ex. `"red"`, `(10,5)`)
+    pub fn command(&self, color: Option<String>, dash: Option<String>) -> String {
+        let line_style = format!("{}{}",
+                                 color.clone().map(|c| format!(" lc {}", c)).unwrap_or_default(),
+                                 dash.map(|d| format!(" dt {}", d)).unwrap_or_default());
+        match self.shape {
+            Shape::Label(ref text, (x, y)) => {
+                format!("set label \"{}\" at first {}, first {} front{}\n",
+                        text,
+                        x,
+                        y,
+                        color.map(|c| format!(" textcolor {}", c)).unwrap_or_default())
+            }
+            Shape::Arrow((x1, y1), (x2, y2)) => {
+                format!("set arrow from first {}, first {} to first {}, first {} front{}\n",
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        line_style)
+            }
+            Shape::HLine(y) => {
+                format!("set arrow from graph 0, first {} to graph 1, first {} nohead front{}\n",
+                        y,
+                        y,
+                        line_style)
+            }
+            Shape::VLine(x) => {
+                format!("set arrow from first {}, graph 0 to first {}, graph 1 nohead front{}\n",
+                        x,
+                        x,
+                        line_style)
+            }
+            Shape::Span(x1, x2) => {
+                format!("set object rect from first {}, graph 0 to first {}, graph 1 behind fc \
+                         {} fs transparent solid 0.2 noborder\n",
+                        x1,
+                        x2,
+                        color.unwrap_or_else(|| "\"gray\"".to_string()))
+            }
+        }
+    }
+}
+
+impl ReadError {
+    pub fn message(&self) -> String {
+        match *self {
+            ReadError::Io(ref e) => e.to_string(),
+            ReadError::Invalid(number, ref row) => {
+                format!("line {}: invalid annotation \"{}\".", number, row)
+            }
+        }
+    }
+}
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> ReadError {
+        ReadError::Io(e)
+    }
+}
+
+/// read annotations from rows of `KIND,SPEC` as `hline,99.9,red`. empty rows and rows starting
+/// with `#` are skipped.
+pub fn read<R: BufRead>(reader: R,
+                        x_time: Option<&TimeFormat>)
+                        -> Result<Vec<Annotation>, ReadError> {
+    let mut annotations = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once(',').and_then(|(kind, spec)| {
+            Annotation::new(kind.trim(), spec, x_time)
+        }) {
+            Some(a) => annotations.push(a),
+            None => return Err(ReadError::Invalid(number + 1, line.to_string())),
+        }
+    }
+    Ok(annotations)
+}
+
+/// x value in seconds for time axis, as gnuplot takes time coordinates.
+fn x_value(arg: &str, x_time: Option<&TimeFormat>) -> Option<f64> {
+    match x_time {
+        Some(time) => time.parse(arg).map(|v| v / time.scale().unwrap_or(1) as f64),
+        None => arg.trim().parse::<f64>().ok(),
+    }
+    .filter(|v| v.is_finite())
+}
+
+#[test]
+fn annotation_test() {
+    let label = Annotation::new("label", "p99 at 3, 99.9", None).unwrap();
+    assert_eq!(label.shape, Shape::Label("p99".to_string(), (3.0, 99.9)));
+    assert_eq!(label.command(Some("\"red\"".to_string()), None),
+               "set label \"p99\" at first 3, first 99.9 front textcolor \"red\"\n".to_string());
+    let arrow = Annotation::new("arrow", "1,2:3,4,rgb(0,0,255),2", None).unwrap();
+    assert_eq!(arrow.shape, Shape::Arrow((1.0, 2.0), (3.0, 4.0)));
+    assert_eq!((arrow.color, arrow.dash),
+               (Some("rgb(0,0,255)".to_string()), Some("2".to_string())));
+    assert_eq!(Annotation::new("hline", "0.5", None).unwrap().command(None, Some("2".to_string())),
+               "set arrow from graph 0, first 0.5 to graph 1, first 0.5 nohead front dt 2\n"
+                   .to_string());
+    let time = TimeFormat::new("%Y-%m-%dT%H:%M:%S");
+    assert_eq!(Annotation::new("span", "1970-01-01T00:01:00:1970-01-01T00:02:00", Some(&time))
+                   .map(|a| a.shape),
+               Some(Shape::Span(60.0, 120.0)));
+    assert_eq!(Annotation::new("vline", "1500", Some(&TimeFormat::new("epoch-ms")))
+                   .map(|a| a.shape),
+               Some(Shape::VLine(1.5)));
+    assert_eq!(Annotation::new("vline", "2017-03-04", None), None);
+    assert_eq!(Annotation::new("label", "p99 3,99.9", None), None);
+    assert_eq!(Annotation::new("hline", "1,red,2,3", None), None);
+    let rows = "# deploys\nvline,10,gray\n\nspan,1:2\n";
+    assert_eq!(read(io::Cursor::new(rows), None).map(|a| a.len()).ok(), Some(2));
+    assert_eq!(read(io::Cursor::new("vline,1\ncircle,1,2\n"), None).map_err(|e| e.message()),
+               Err("line 2: invalid annotation \"circle,1,2\".".to_string()));
+}
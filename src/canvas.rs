@@ -0,0 +1,88 @@
+/// a length of canvas side.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Length {
+    Inch(f64),
+    Cm(f64),
+    Px(f64),
+}
+/// canvas (width, height), as `--size 3.5in,2.5in`.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Size {
+    pub width: Length,
+    pub height: Length,
+}
+
+impl Length {
+    /// number followed by `in`, `cm` or `px`.
+    pub fn new(arg: &str) -> Option<Length> {
+        let arg = arg.trim();
+        let unit = arg.char_indices().rev().nth(1).map(|(i, _)| &arg[i..])?;
+        let value = arg[..arg.len() - unit.len()]
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v > 0.0)?;
+        match unit {
+            "in" => Some(Length::Inch(value)),
+            "cm" => Some(Length::Cm(value)),
+            "px" => Some(Length::Px(value)),
+            _ => None,
+        }
+    }
+    pub fn inches(&self, dpi: f64) -> f64 {
+        match *self {
+            Length::Inch(v) => v,
+            Length::Cm(v) => v / 2.54,
+            Length::Px(v) => v / dpi,
+        }
+    }
+}
+impl Size {
+    pub fn new(arg: &str) -> Option<Size> {
+        let mut sides = arg.splitn(2, ',');
+        Some(Size {
+            width: Length::new(sides.next()?)?,
+            height: Length::new(sides.next()?)?,
+        })
+    }
+    pub fn inches(width: f64, height: f64) -> Size {
+        Size {
+            width: Length::Inch(width),
+            height: Length::Inch(height),
+        }
+    }
+    /// `size` of gnuplot's terminal. raster terminals take pixels at `dpi`, others take inches.
+    pub fn terminal_size(&self, is_raster: bool, dpi: f64) -> String {
+        let (width, height) = (self.width.inches(dpi), self.height.inches(dpi));
+        if is_raster {
+            format!("{},{}", (width * dpi).round(), (height * dpi).round())
+        } else {
+            format!("{}in,{}in", round(width), round(height))
+        }
+    }
+}
+
+/// scale of fonts and lines in raster output at `dpi`, which gnuplot draws as at 72 dpi.
+pub fn raster_scale(dpi: f64) -> f64 {
+    round(dpi / 72.0)
+}
+
+/// drop float noise of unit conversion. (ex. 3.1496062992125986 -> 3.1496)
+fn round(inches: f64) -> f64 {
+    (inches * 10000.0).round() / 10000.0
+}
+
+#[test]
+fn size_test() {
+    assert_eq!(Size::new("3.5in,2.5in"), Some(Size::inches(3.5, 2.5)));
+    assert_eq!(Size::new("8cm,6cm").map(|s| s.terminal_size(false, 300.0)),
+               Some("3.1496in,2.3622in".to_string()));
+    assert_eq!(Size::new("600px,2in").map(|s| s.terminal_size(false, 300.0)),
+               Some("2in,2in".to_string()));
+    assert_eq!(Size::new("3.5in,2.5in").map(|s| s.terminal_size(true, 200.0)),
+               Some("700,500".to_string()));
+    assert_eq!(raster_scale(144.0), 2.0);
+    assert_eq!(Size::new("3.5in"), None);
+    assert_eq!(Size::new("3.5,2.5"), None);
+    assert_eq!(Size::new("-1in,2in"), None);
+}
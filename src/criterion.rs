@@ -0,0 +1,159 @@
+use serde_json::Value;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path;
+
+/// estimate of criterion.rs benchmark plotted with its confidence interval.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Estimate {
+    Mean,
+    Median,
+    Slope,
+}
+/// a benchmark in `target/criterion`, with (estimate, lower bound, upper bound) in ns.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Benchmark {
+    pub group: String,
+    pub function: Option<String>,
+    pub value: Option<String>,
+    pub estimate: (f64, f64, f64),
+}
+
+impl Estimate {
+    pub fn new(arg: &str) -> Option<Estimate> {
+        match arg {
+            "mean" => Some(Estimate::Mean),
+            "median" => Some(Estimate::Median),
+            "slope" => Some(Estimate::Slope),
+            _ => None,
+        }
+    }
+    fn key(&self) -> &'static str {
+        match *self {
+            Estimate::Mean => "mean",
+            Estimate::Median => "median",
+            Estimate::Slope => "slope",
+        }
+    }
+}
+
+/// latest results (`new/benchmark.json` and `new/estimates.json`) under `directory`.
+/// benchmarks without the estimate (ex. slope of flat sampling) are skipped.
+pub fn read_benchmarks(directory: &path::Path, estimate: &Estimate) -> io::Result<Vec<Benchmark>> {
+    let mut found = Vec::new();
+    find_results(directory, &mut found)?;
+    found.sort();
+    let mut benchmarks = Vec::new();
+    for result in found {
+        let benchmark = read_json(&result.join("benchmark.json"))?;
+        let estimates = read_json(&result.join("estimates.json"))?;
+        if let Some(b) = parse_benchmark(&benchmark, &estimates, estimate) {
+            benchmarks.push(b);
+        }
+    }
+    Ok(benchmarks)
+}
+fn find_results(directory: &path::Path, found: &mut Vec<path::PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?.path();
+        if !entry.is_dir() || entry.file_name().is_some_and(|n| n == "report") {
+            continue;
+        }
+        if entry.file_name().is_some_and(|n| n == "new") &&
+           entry.join("benchmark.json").is_file() {
+            found.push(entry);
+        } else {
+            find_results(&entry, found)?;
+        }
+    }
+    Ok(())
+}
+fn read_json(file: &path::Path) -> io::Result<Value> {
+    serde_json::from_reader(io::BufReader::new(File::open(file)?))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}: {}", file, e)))
+}
+fn parse_benchmark(benchmark: &Value, estimates: &Value, estimate: &Estimate) -> Option<Benchmark> {
+    let text = |key: &str| benchmark[key].as_str().map(|s| s.to_string());
+    let e = &estimates[estimate.key()];
+    let interval = &e["confidence_interval"];
+    Some(Benchmark {
+        group: text("group_id")?,
+        function: text("function_id"),
+        value: text("value_str"),
+        estimate: (e["point_estimate"].as_f64()?,
+                   interval["lower_bound"].as_f64()?,
+                   interval["upper_bound"].as_f64()?),
+    })
+}
+
+/// columns (x, estimate, lower, upper) for each function of groups, titled by `group/function`
+/// (or `function` in a single group). x is the parameter of benchmark ID, or the position of the
+/// benchmark if some of parameters are not numbers.
+pub fn series(benchmarks: &[Benchmark]) -> Vec<(String, Vec<Vec<f64>>)> {
+    let mut keys: Vec<(&String, &Option<String>)> = Vec::new();
+    for b in benchmarks {
+        if !keys.contains(&(&b.group, &b.function)) {
+            keys.push((&b.group, &b.function));
+        }
+    }
+    let single_group = keys.iter().all(|k| k.0 == keys[0].0);
+    keys.iter()
+        .map(|&(group, function)| {
+            let members = benchmarks.iter()
+                .filter(|b| &b.group == group && &b.function == function)
+                .collect::<Vec<_>>();
+            let parameters = members.iter()
+                .map(|b| b.value.as_ref().and_then(|v| v.parse::<f64>().ok()))
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_else(|| (1..members.len() + 1).map(|i| i as f64).collect());
+            let mut rows = parameters.into_iter()
+                .zip(members.iter())
+                .map(|(x, b)| vec![x, b.estimate.0, b.estimate.1, b.estimate.2])
+                .collect::<Vec<_>>();
+            rows.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap_or(::std::cmp::Ordering::Equal));
+            let title = match (single_group, function) {
+                (true, Some(f)) => f.clone(),
+                (_, Some(f)) => format!("{}/{}", group, f),
+                (_, None) => group.clone(),
+            };
+            (title, (0..4).map(|c| rows.iter().map(|r| r[c]).collect()).collect())
+        })
+        .collect()
+}
+
+#[test]
+fn read_benchmarks_test() {
+    use mktemp::Temp;
+    use std::io::Write;
+    let root = Temp::new_dir().unwrap();
+    let write = |id: &str, function: &str, value: &str, mean: f64| {
+        let new = root.as_ref().join(id).join("new");
+        fs::create_dir_all(&new).unwrap();
+        write!(File::create(new.join("benchmark.json")).unwrap(),
+               "{{\"group_id\":\"fib\",\"function_id\":\"{}\",\"value_str\":\"{}\"}}",
+               function,
+               value)
+            .unwrap();
+        write!(File::create(new.join("estimates.json")).unwrap(),
+               "{{\"mean\":{{\"confidence_interval\":{{\"lower_bound\":{},\"upper_bound\":{}}},\
+                \"point_estimate\":{}}},\"slope\":null}}",
+               mean - 1.0,
+               mean + 1.0,
+               mean)
+            .unwrap();
+    };
+    write("fib/iterative/20", "iterative", "20", 5.0);
+    write("fib/recursive/20", "recursive", "20", 50.0);
+    write("fib/recursive/10", "recursive", "10", 10.0);
+    fs::create_dir_all(root.as_ref().join("fib/report")).unwrap();
+    let benchmarks = read_benchmarks(root.as_ref(), &Estimate::Mean).unwrap();
+    assert_eq!(benchmarks.len(), 3);
+    let series = series(&benchmarks);
+    assert_eq!(series[0],
+               ("iterative".to_string(), vec![vec![20.0], vec![5.0], vec![4.0], vec![6.0]]));
+    assert_eq!(series[1].0, "recursive".to_string());
+    assert_eq!(series[1].1[0], vec![10.0, 20.0]);
+    assert_eq!(series[1].1[1], vec![10.0, 50.0]);
+    assert!(read_benchmarks(root.as_ref(), &Estimate::Slope).unwrap().is_empty());
+}
@@ -0,0 +1,688 @@
+use mktemp::Temp;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::iter;
+use expr::Expr;
+use std::path;
+use timefmt::TimeFormat;
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum Delimiter {
+    Char(char),
+    Whitespace,
+}
+/// how values in input files are read on Rust side.
+#[derive(Debug,Clone)]
+pub struct DataFormat {
+    pub delimiter: Delimiter,
+    pub x_time: Option<TimeFormat>,
+    pub skip_rows: usize,
+    pub comment: Option<String>,
+    pub missing: Option<String>,
+}
+
+impl Delimiter {
+    pub fn new(arg: &str) -> Option<Delimiter> {
+        match arg {
+            "comma" => Some(Delimiter::Char(',')),
+            "tab" | "\\t" => Some(Delimiter::Char('\t')),
+            "semicolon" => Some(Delimiter::Char(';')),
+            "whitespace" | "space" => Some(Delimiter::Whitespace),
+            _ if arg.chars().count() == 1 => arg.chars().next().map(Delimiter::Char),
+            _ => None,
+        }
+    }
+    /// separator given to gnuplot's `set datafile separator`.
+    pub fn separator(&self) -> String {
+        match *self {
+            Delimiter::Char('\t') => r"\t".to_string(),
+            Delimiter::Char(c) => c.to_string(),
+            Delimiter::Whitespace => "whitespace".to_string(),
+        }
+    }
+    pub fn split(&self, line: &str) -> Vec<String> {
+        match *self {
+            Delimiter::Char(c) => split_fields(line, |s| s.starts_with(c)),
+            Delimiter::Whitespace => {
+                let mut fields = split_fields(line.trim(), |s| s.starts_with(char::is_whitespace));
+                fields.retain(|f| !f.is_empty());
+                fields
+            }
+        }
+    }
+    /// join fields, quoting ones which contain delimiter.
+    pub fn join(&self, fields: &[String]) -> String {
+        let needs_quote = |f: &str| match *self {
+            Delimiter::Char(c) => f.contains(c) || f.contains('"'),
+            Delimiter::Whitespace => {
+                f.is_empty() || f.contains(char::is_whitespace) || f.contains('"')
+            }
+        };
+        let sep = match *self {
+            Delimiter::Char(c) => c.to_string(),
+            Delimiter::Whitespace => " ".to_string(),
+        };
+        fields.iter()
+            .map(|f| if needs_quote(f) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.clone()
+            })
+            .collect::<Vec<_>>()
+            .join(sep.as_str())
+    }
+}
+impl DataFormat {
+    pub fn new(delimiter: Delimiter) -> DataFormat {
+        DataFormat {
+            delimiter,
+            x_time: None,
+            skip_rows: 0,
+            comment: None,
+            missing: None,
+        }
+    }
+    /// whether `line` (0-origin `number`) is a data row, not a header or a comment.
+    pub fn is_data_line(&self, number: usize, line: &str) -> bool {
+        number >= self.skip_rows && !self.is_comment(line)
+    }
+    fn is_comment(&self, line: &str) -> bool {
+        let comment = self.comment.as_deref().unwrap_or("#!");
+        line.trim_start().starts_with(|c| comment.contains(c))
+    }
+    fn value(&self, cell: &str, is_x: bool) -> Option<f64> {
+        if self.missing.as_ref().map(|m| m == cell.trim()).unwrap_or(false) {
+            return None;
+        }
+        match self.x_time {
+            Some(ref time) if is_x => time.parse(cell),
+            _ => cell.trim().parse::<f64>().ok(),
+        }
+    }
+}
+
+/// read numeric columns (1-origin, as in gnuplot's `using`) from a delimited text file.
+/// first column is regarded as x. rows in which any requested column is missing or not a number
+/// (header, comment, ..) are skipped. column 0 is the index of data row.
+pub fn read_columns(file: &str,
+                    format: &DataFormat,
+                    columns: &[Expr])
+                    -> io::Result<Vec<Vec<f64>>> {
+    let reader = BufReader::new(File::open(file)?);
+    parse_columns(reader, format, columns)
+}
+pub fn parse_columns<R: BufRead>(reader: R,
+                                 format: &DataFormat,
+                                 columns: &[Expr])
+                                 -> io::Result<Vec<Vec<f64>>> {
+    let mut table = vec![Vec::new(); columns.len()];
+    for_each_row(reader, format, columns, |_, values| {
+        for (column, value) in table.iter_mut().zip(values) {
+            column.push(value);
+        }
+    })?;
+    Ok(table)
+}
+/// stream values of `columns` in each row to `f`, with the index of the row among data rows.
+/// rows skipped as in `parse_columns` are not given, but counted in indices.
+pub fn for_each_row<R, F>(reader: R,
+                          format: &DataFormat,
+                          columns: &[Expr],
+                          mut f: F)
+                          -> io::Result<()>
+    where R: BufRead,
+          F: FnMut(usize, Vec<f64>)
+{
+    let (mut index, mut row) = (0, 0);
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if !format.is_data_line(number, line.as_str()) {
+            continue;
+        }
+        let cells = format.delimiter.split(line.as_str());
+        let cell = |c: u32, is_x: bool| if c == 0 {
+            Some(row as f64)
+        } else {
+            cells.get(c as usize - 1).and_then(|cell| format.value(cell, is_x))
+        };
+        let values = columns.iter()
+            .enumerate()
+            .map(|(i, e)| match e.column() {
+                Some(c) => cell(c, i == 0),
+                None => e.eval(&|c| cell(c, false)),
+            })
+            .collect::<Option<Vec<_>>>();
+        if let Some(values) = values {
+            row += 1;
+            f(index, values);
+        }
+        index += 1;
+    }
+    Ok(())
+}
+/// number of data rows, which are indexed by `for_each_row`.
+pub fn count_rows<R: BufRead>(reader: R, format: &DataFormat) -> io::Result<usize> {
+    let mut rows = 0;
+    for (number, line) in reader.lines().enumerate() {
+        if format.is_data_line(number, line?.as_str()) {
+            rows += 1;
+        }
+    }
+    Ok(rows)
+}
+/// column names. header is the last skipped row if rows are skipped, otherwise the first row.
+pub fn read_header(file: &str, format: &DataFormat) -> io::Result<Vec<String>> {
+    let reader = BufReader::new(File::open(file)?);
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let is_header = if format.skip_rows > 0 {
+            number + 1 == format.skip_rows
+        } else {
+            !format.is_comment(line.as_str()) && !line.trim().is_empty()
+        };
+        if is_header {
+            return Ok(format.delimiter.split(line.as_str()));
+        }
+    }
+    Ok(Vec::new())
+}
+/// whether `fields` of the first data row are column names, not values.
+pub fn is_header_row(fields: &[String]) -> bool {
+    fields.iter().all(|f| f.trim().parse::<f64>().is_err())
+}
+/// a column referred by axes which cannot be plotted. rows are 1-origin line numbers.
+#[derive(Debug,Clone,PartialEq)]
+pub enum ColumnError {
+    /// (column, number of columns in the file)
+    NotFound(u32, usize),
+    /// (column) every cell is missing.
+    Empty(u32),
+    /// (column, first row, cell) no cell is a number.
+    NoNumbers(u32, usize, String),
+    /// (column, row, cell) a cell is not a number, with `strict`.
+    NotNumeric(u32, usize, String),
+}
+impl ColumnError {
+    pub fn message(&self) -> String {
+        match *self {
+            ColumnError::NotFound(column, width) => {
+                format!("column {} is not found. (the file has {} columns)", column, width)
+            }
+            ColumnError::Empty(column) => format!("column {} has no values.", column),
+            ColumnError::NoNumbers(column, row, ref cell) => {
+                format!("column {} has no numbers. (first bad row {}: \"{}\")",
+                        column,
+                        row,
+                        cell)
+            }
+            ColumnError::NotNumeric(column, row, ref cell) => {
+                format!("column {} is not a number in row {}: \"{}\"", column, row, cell)
+            }
+        }
+    }
+}
+/// check that each of `columns` (column, is x) exists and has numbers in some rows, or in all of
+/// non-missing cells if `strict`. header row is not checked, and column 0 (row index) is always
+/// valid.
+pub fn check_columns<R: BufRead>(reader: R,
+                                 format: &DataFormat,
+                                 columns: &[(u32, bool)],
+                                 strict: bool)
+                                 -> io::Result<Option<ColumnError>> {
+    let mut width = 0;
+    let mut has_number = vec![false; columns.len()];
+    let mut has_value = vec![false; columns.len()];
+    let mut first_bad = vec![None; columns.len()];
+    let mut is_first = format.skip_rows == 0;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if !format.is_data_line(number, line.as_str()) || line.trim().is_empty() {
+            continue;
+        }
+        let cells = format.delimiter.split(line.as_str());
+        if is_first && is_header_row(&cells) {
+            is_first = false;
+            continue;
+        }
+        is_first = false;
+        width = width.max(cells.len());
+        for (i, &(column, is_x)) in columns.iter().enumerate().filter(|&(_, &(c, _))| c > 0) {
+            let cell = match cells.get(column as usize - 1) {
+                Some(cell) if !cell.trim().is_empty() &&
+                              format.missing.as_deref() != Some(cell.trim()) => cell,
+                _ => continue,
+            };
+            has_value[i] = true;
+            if format.value(cell, is_x).is_some() {
+                has_number[i] = true;
+            } else if first_bad[i].is_none() {
+                first_bad[i] = Some((number + 1, cell.trim().to_string()));
+            }
+        }
+    }
+    Ok(columns.iter()
+        .enumerate()
+        .filter_map(|(i, &(column, _))| if column == 0 {
+            None
+        } else if column as usize > width {
+            Some(ColumnError::NotFound(column, width))
+        } else if !has_value[i] {
+            Some(ColumnError::Empty(column))
+        } else {
+            match first_bad[i].clone() {
+                Some((row, cell)) if !has_number[i] => {
+                    Some(ColumnError::NoNumbers(column, row, cell))
+                }
+                Some((row, cell)) if strict => Some(ColumnError::NotNumeric(column, row, cell)),
+                _ => None,
+            }
+        })
+        .next())
+}
+/// guess delimiter from first lines of a file. (tab, semicolon, comma, then whitespace)
+pub fn detect_delimiter(file: &str, format: &DataFormat) -> io::Result<Delimiter> {
+    let reader = BufReader::new(File::open(file)?);
+    let mut lines = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if format.is_data_line(number, line.as_str()) && !line.trim().is_empty() {
+            lines.push(line);
+        }
+        if lines.len() >= 10 {
+            break;
+        }
+    }
+    Ok(guess_delimiter(&lines))
+}
+fn guess_delimiter(lines: &[String]) -> Delimiter {
+    let candidates = [Delimiter::Char('\t'),
+                      Delimiter::Char(';'),
+                      Delimiter::Char(','),
+                      Delimiter::Whitespace];
+    candidates.iter()
+        .find(|d| {
+            let counts = lines.iter().map(|l| d.split(l).len()).collect::<Vec<_>>();
+            counts.first().map(|&n| n > 1 && counts.iter().all(|&c| c == n)).unwrap_or(false)
+        })
+        .cloned()
+        .unwrap_or(Delimiter::Char(','))
+}
+/// copy of a file in `delimiter`, with decimal commas (`1,5`) replaced by points if
+/// `decimal_comma`, so that gnuplot and Rust side read every input in the same way.
+pub fn normalize(file: &str,
+                 format: &DataFormat,
+                 delimiter: &Delimiter,
+                 decimal_comma: bool,
+                 directory: &path::Path)
+                 -> io::Result<Temp> {
+    let reader = BufReader::new(File::open(file)?);
+    let temp_file = Temp::new_file_in(directory)?;
+    {
+        let writer = io::BufWriter::new(File::create(temp_file.as_ref())?);
+        normalize_rows(reader, writer, format, delimiter, decimal_comma)?;
+    }
+    Ok(temp_file)
+}
+fn normalize_rows<R: BufRead, W: Write>(reader: R,
+                                        mut writer: W,
+                                        format: &DataFormat,
+                                        delimiter: &Delimiter,
+                                        decimal_comma: bool)
+                                        -> io::Result<()> {
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if format.is_comment(line.as_str()) || line.trim().is_empty() {
+            writeln!(writer, "{}", line)?;
+            continue;
+        }
+        // skipped rows (header) are split too, to be read in the new delimiter
+        let is_data = format.is_data_line(number, line.as_str());
+        let fields = format.delimiter
+            .split(line.as_str())
+            .into_iter()
+            .map(|f| if decimal_comma && is_data &&
+                        f.trim().replace(',', ".").parse::<f64>().is_ok() {
+                f.replace(',', ".")
+            } else {
+                f
+            })
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", delimiter.join(&fields))?;
+    }
+    writer.flush()
+}
+/// rows of each group with its value.
+pub type Groups = Vec<(String, Vec<String>)>;
+/// rows of a long-form file partitioned by the value in `column`, in order of first appearance.
+/// skipped rows (header) are returned separately to be copied into each group. if no rows are
+/// skipped, the first row is dropped when it is a header, not to be a group of its own.
+pub fn partition_rows<R: BufRead>(reader: R,
+                                  format: &DataFormat,
+                                  column: u32)
+                                  -> io::Result<(Vec<String>, Groups)> {
+    let mut header = Vec::new();
+    let mut groups: Groups = Vec::new();
+    let mut is_first = true;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if number < format.skip_rows {
+            header.push(line);
+            continue;
+        }
+        if !format.is_data_line(number, line.as_str()) || line.trim().is_empty() {
+            continue;
+        }
+        let fields = format.delimiter.split(line.as_str());
+        if format.skip_rows == 0 && is_first && is_header_row(&fields) {
+            is_first = false;
+            continue;
+        }
+        is_first = false;
+        let value = match fields.get(column as usize - 1) {
+            Some(value) => value.trim().to_string(),
+            None => continue,
+        };
+        match groups.iter().position(|g| g.0 == value) {
+            Some(i) => groups[i].1.push(line),
+            None => groups.push((value, vec![line])),
+        }
+    }
+    Ok((header, groups))
+}
+/// write each group of `partition_rows` into a temporary file in `directory`.
+pub fn split_groups(file: &str,
+                    format: &DataFormat,
+                    column: u32,
+                    directory: &path::Path)
+                    -> io::Result<Vec<(String, Temp)>> {
+    let reader = BufReader::new(File::open(file)?);
+    let (header, groups) = partition_rows(reader, format, column)?;
+    let mut files = Vec::new();
+    for (value, lines) in groups {
+        let temp_file = Temp::new_file_in(directory)?;
+        {
+            let mut writer = io::BufWriter::new(File::create(temp_file.as_ref())?);
+            for line in header.iter().chain(lines.iter()) {
+                writeln!(writer, "{}", line)?;
+            }
+        }
+        files.push((value, temp_file));
+    }
+    Ok(files)
+}
+/// header rows and data rows of a file, split into fields.
+pub type Rows = Vec<Vec<String>>;
+/// header rows are skipped rows, or the first row if no rows are skipped and it is a header.
+fn read_rows<R: BufRead>(reader: R, format: &DataFormat) -> io::Result<(Rows, Rows)> {
+    let (mut header, mut rows) = (Vec::new(), Vec::new());
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if number < format.skip_rows {
+            header.push(format.delimiter.split(line.as_str()));
+        } else if format.is_data_line(number, line.as_str()) && !line.trim().is_empty() {
+            rows.push(format.delimiter.split(line.as_str()));
+        }
+    }
+    if format.skip_rows == 0 && rows.first().is_some_and(|r| is_header_row(r)) {
+        header.push(rows.remove(0));
+    }
+    Ok((header, rows))
+}
+/// align rows of tables on values in `keys` column of each table. rows of the first table whose
+/// key is not found in all of other tables are dropped.
+/// joined row is the key followed by fields of each table, and column `c` of N-th table is
+/// `offsets[N - 1] + c` in it.
+pub fn join_rows(tables: &[(Rows, Rows)], keys: &[u32]) -> (Rows, Vec<u32>) {
+    let widths = tables.iter()
+        .map(|(header, rows)| {
+            header.iter().chain(rows.iter()).map(|r| r.len()).max().unwrap_or(0) as u32
+        })
+        .collect::<Vec<_>>();
+    let offsets = widths.iter()
+        .scan(1, |offset, &w| {
+            let o = *offset;
+            *offset += w;
+            Some(o)
+        })
+        .collect::<Vec<_>>();
+    let key_of = |row: &[String], key: u32| row.get(key as usize - 1).map(|k| k.trim().to_string());
+    let indices = tables.iter()
+        .zip(keys.iter())
+        .map(|((_, rows), &key)| {
+            let mut index = HashMap::new();
+            for row in rows {
+                if let Some(k) = key_of(row, key) {
+                    index.entry(k).or_insert(row);
+                }
+            }
+            index
+        })
+        .collect::<Vec<_>>();
+    let joined_row = |key: String, rows: Vec<&Vec<String>>| {
+        let mut joined = vec![key];
+        for (row, &width) in rows.into_iter().zip(widths.iter()) {
+            let padding = iter::repeat(String::new());
+            joined.extend(row.iter().cloned().chain(padding).take(width as usize));
+        }
+        joined
+    };
+    let empty = Vec::new();
+    let mut joined = (0..tables[0].0.len())
+        .map(|i| {
+            let key = key_of(&tables[0].0[i], keys[0]).unwrap_or_default();
+            joined_row(key, tables.iter().map(|t| t.0.get(i).unwrap_or(&empty)).collect())
+        })
+        .collect::<Vec<_>>();
+    for row in &tables[0].1 {
+        if let Some(key) = key_of(row, keys[0]) {
+            if let Some(rows) = indices.iter().map(|index| index.get(&key).cloned()).collect() {
+                joined.push(joined_row(key, rows));
+            }
+        }
+    }
+    (joined, offsets)
+}
+/// write rows of `files` joined by `join_rows` into a temporary file in `directory`.
+pub fn join_files(files: &[String],
+                  keys: &[u32],
+                  format: &DataFormat,
+                  directory: &path::Path)
+                  -> io::Result<(Temp, Vec<u32>)> {
+    let tables = files.iter()
+        .map(|f| read_rows(BufReader::new(File::open(f)?), format))
+        .collect::<io::Result<Vec<_>>>()?;
+    let (joined, offsets) = join_rows(&tables, keys);
+    let temp_file = Temp::new_file_in(directory)?;
+    {
+        let mut writer = io::BufWriter::new(File::create(temp_file.as_ref())?);
+        for row in joined {
+            writeln!(writer, "{}", format.delimiter.join(&row))?;
+        }
+    }
+    Ok((temp_file, offsets))
+}
+/// split a line at positions where `is_delimiter` matches.
+/// delimiter in double-quoted field (ex. `"Mar 4, 2017"`) does not split it.
+fn split_fields<F>(line: &str, is_delimiter: F) -> Vec<String>
+    where F: Fn(&str) -> bool
+{
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            if quoted && rest[1..].starts_with('"') {
+                field.push('"');
+                rest = &rest[2..];
+                continue;
+            }
+            quoted = !quoted;
+        } else if !quoted && is_delimiter(rest) {
+            fields.push(field);
+            field = String::new();
+        } else {
+            field.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    fields.push(field);
+    fields
+}
+/// write columns into a temporary file in `directory`, to be referred as `Series.data_file`.
+pub fn write_columns(directory: &path::Path,
+                     delimiter: &Delimiter,
+                     columns: &[Vec<f64>])
+                     -> io::Result<Temp> {
+    let temp_file = Temp::new_file_in(directory)?;
+    {
+        let mut writer = io::BufWriter::new(File::create(temp_file.as_ref())?);
+        let rows = columns.iter().map(|c| c.len()).min().unwrap_or(0);
+        for i in 0..rows {
+            let row = columns.iter().map(|c| c[i].to_string()).collect::<Vec<_>>();
+            writeln!(writer, "{}", delimiter.join(&row))?;
+        }
+    }
+    Ok(temp_file)
+}
+
+#[test]
+fn parse_columns_test() {
+    let input = "x,y,z\n1,2,3\n# comment\n4,5.5,6\n7,,9\n";
+    let format = DataFormat::new(Delimiter::Char(','));
+    let columns = |cs: &[&str]| cs.iter().map(|c| Expr::new(c).unwrap()).collect::<Vec<_>>();
+    let table = parse_columns(io::Cursor::new(input), &format, &columns(&["1", "3"])).unwrap();
+    assert_eq!(table, vec![vec![1.0, 4.0, 7.0], vec![3.0, 6.0, 9.0]]);
+    let table = parse_columns(io::Cursor::new(input), &format, &columns(&["1", "2"])).unwrap();
+    assert_eq!(table, vec![vec![1.0, 4.0], vec![2.0, 5.5]]);
+    let table = parse_columns(io::Cursor::new(input), &format, &columns(&["4"])).unwrap();
+    assert_eq!(table, vec![Vec::<f64>::new()]);
+    let table = parse_columns(io::Cursor::new(input), &format, &columns(&["0", "($3-$1)"]))
+        .unwrap();
+    assert_eq!(table, vec![vec![0.0, 1.0, 2.0], vec![2.0, 2.0, 2.0]]);
+    let input = "unit: ms\nx y\n1 NA\n% 2 4\n  3   9\n";
+    let mut format = DataFormat::new(Delimiter::Whitespace);
+    format.skip_rows = 1;
+    format.comment = Some("%".to_string());
+    format.missing = Some("NA".to_string());
+    let table = parse_columns(io::Cursor::new(input), &format, &[Expr::Column(1), Expr::Column(2)])
+        .unwrap();
+    assert_eq!(table, vec![vec![3.0], vec![9.0]]);
+    let mut indices = Vec::new();
+    for_each_row(io::Cursor::new(input), &format, &[Expr::Column(2)], |i, _| indices.push(i))
+        .unwrap();
+    assert_eq!(indices, vec![2]);
+    assert_eq!(count_rows(io::Cursor::new(input), &format).unwrap(), 3);
+}
+#[test]
+fn check_columns_test() {
+    let input = "x,y,label\n1,2,a\n2,NA,b\n3,x,c\n4,5\n";
+    let mut format = DataFormat::new(Delimiter::Char(','));
+    format.missing = Some("NA".to_string());
+    let check = |format: &DataFormat, columns: &[(u32, bool)], strict: bool| {
+        check_columns(io::Cursor::new(input), format, columns, strict).unwrap()
+    };
+    assert_eq!(check(&format, &[(1, true), (2, false)], false), None);
+    assert_eq!(check(&format, &[(0, true), (1, false)], true), None);
+    assert_eq!(check(&format, &[(1, true), (2, false)], true),
+               Some(ColumnError::NotNumeric(2, 4, "x".to_string())));
+    assert_eq!(check(&format, &[(1, true), (9, false)], false),
+               Some(ColumnError::NotFound(9, 3)));
+    assert_eq!(check(&format, &[(3, false)], false),
+               Some(ColumnError::NoNumbers(3, 2, "a".to_string())));
+    format.missing = Some("a".to_string());
+    assert_eq!(check(&format, &[(3, false)], false),
+               Some(ColumnError::NoNumbers(3, 3, "b".to_string())));
+}
+#[test]
+fn partition_rows_test() {
+    let input = "config,x,y\nfast,1,2\nslow,1,5\n# comment\nfast,2,3\n\"a,b\",1,1\nshort\n";
+    let mut format = DataFormat::new(Delimiter::Char(','));
+    format.skip_rows = 1;
+    let (header, groups) = partition_rows(io::Cursor::new(input), &format, 1).unwrap();
+    assert_eq!(header, vec!["config,x,y".to_string()]);
+    let values = groups.iter().map(|g| g.0.as_str()).collect::<Vec<_>>();
+    assert_eq!(values, vec!["fast", "slow", "a,b", "short"]);
+    assert_eq!(groups[0].1, vec!["fast,1,2".to_string(), "fast,2,3".to_string()]);
+    let (_, groups) = partition_rows(io::Cursor::new(input), &format, 2).unwrap();
+    assert_eq!(groups.iter().map(|g| g.1.len()).collect::<Vec<_>>(), vec![3, 1]);
+    // header is not skipped
+    format.skip_rows = 0;
+    let (header, groups) = partition_rows(io::Cursor::new(input), &format, 1).unwrap();
+    assert!(header.is_empty());
+    assert_eq!(groups.iter().map(|g| g.0.as_str()).collect::<Vec<_>>(),
+               vec!["fast", "slow", "a,b", "short"]);
+}
+#[test]
+fn read_rows_test() {
+    let format = DataFormat::new(Delimiter::Char(','));
+    let (header, rows) = read_rows(io::Cursor::new("n,ms\n1,10\n"), &format).unwrap();
+    assert_eq!((header.len(), rows.len()), (1, 1));
+    let (header, rows) = read_rows(io::Cursor::new("1,10\n2,20\n"), &format).unwrap();
+    assert_eq!((header.len(), rows.len()), (0, 2));
+}
+#[test]
+fn join_rows_test() {
+    let rows = |s: &str| {
+        s.lines().map(|l| l.split(',').map(|f| f.to_string()).collect()).collect::<Rows>()
+    };
+    let baseline = (rows("n,ms"), rows("1,10\n2,20\n3,30"));
+    let branch = (rows("ms,n,note"), rows("5,2\n15,3,slow\n9,4"));
+    let (joined, offsets) = join_rows(&[baseline, branch], &[1, 2]);
+    assert_eq!(offsets, vec![1, 3]);
+    assert_eq!(joined,
+               rows("n,n,ms,ms,n,note\n2,2,20,5,2,\n3,3,30,15,3,slow"));
+}
+#[test]
+fn parse_time_columns_test() {
+    let input = "time,value\n\"Mar 4, 2017\",1\n\"Mar 5, 2017\",2\n";
+    let mut format = DataFormat::new(Delimiter::Char(','));
+    format.x_time = Some(TimeFormat::new("%b %d, %Y"));
+    let table = parse_columns(io::Cursor::new(input), &format, &[Expr::Column(1), Expr::Column(2)])
+        .unwrap();
+    assert_eq!(table, vec![vec![1488585600.0, 1488672000.0], vec![1.0, 2.0]]);
+}
+#[test]
+fn normalize_rows_test() {
+    let input = "# in semicolons\nname;value\na b;1,5\nc;2\n";
+    let mut format = DataFormat::new(Delimiter::Char(';'));
+    format.skip_rows = 2;
+    let mut output = Vec::new();
+    normalize_rows(io::Cursor::new(input), &mut output, &format, &Delimiter::Char('\t'), true)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+               "# in semicolons\nname\tvalue\na b\t1.5\nc\t2\n".to_string());
+    let mut output = Vec::new();
+    normalize_rows(io::Cursor::new(input), &mut output, &format, &Delimiter::Whitespace, false)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(),
+               "# in semicolons\nname value\n\"a b\" 1,5\nc 2\n".to_string());
+}
+#[test]
+fn delimiter_test() {
+    let comma = Delimiter::Char(',');
+    assert_eq!(comma.split("a,\"b,c\",d"), vec!["a", "b,c", "d"]);
+    assert_eq!(comma.split("2017-03-04 05:06:07,1"),
+               vec!["2017-03-04 05:06:07", "1"]);
+    assert_eq!(Delimiter::Char('\t').split("a\t\"say \"\"hi\"\"\"\t"),
+               vec!["a", "say \"hi\"", ""]);
+    assert_eq!(Delimiter::Whitespace.split("  1 \t2  \"a b\""), vec!["1", "2", "a b"]);
+    assert_eq!(comma.join(&["a".to_string(), "b,c".to_string()]),
+               "a,\"b,c\"".to_string());
+    assert_eq!(Delimiter::new("tab"), Some(Delimiter::Char('\t')));
+    assert_eq!(Delimiter::new(";"), Some(Delimiter::Char(';')));
+    assert_eq!(Delimiter::new("auto"), None);
+    assert_eq!(Delimiter::Char('\t').separator(), r"\t".to_string());
+}
+#[test]
+fn guess_delimiter_test() {
+    let lines = |s: &str| s.lines().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(guess_delimiter(&lines("x,y\n1,2\n3,4")), Delimiter::Char(','));
+    assert_eq!(guess_delimiter(&lines("x\ty\n1,5\t2\n3\t4")), Delimiter::Char('\t'));
+    assert_eq!(guess_delimiter(&lines("x;y\n1,5;2,25\n3,1;4,5")), Delimiter::Char(';'));
+    assert_eq!(guess_delimiter(&lines("x y\n1  2\n3 4")), Delimiter::Whitespace);
+    assert_eq!(guess_delimiter(&lines("x\n1\n2")), Delimiter::Char(','));
+}
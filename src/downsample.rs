@@ -0,0 +1,186 @@
+/// decimation of large series before they are handed to gnuplot.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Downsample {
+    Lttb,
+    MinMax,
+}
+/// decimation of rows (x, y, ..) streamed in order, holding at most two buckets of rows.
+/// first and last rows are always kept.
+#[derive(Debug)]
+pub struct Reducer {
+    method: Downsample,
+    /// number of buckets between first and last rows.
+    buckets: usize,
+    /// rows per bucket, in indices of rows.
+    bucket_size: f64,
+    /// (bucket, rows) not yet reduced.
+    pending: Vec<(usize, Vec<Vec<f64>>)>,
+    /// (index, row) pushed most recently, which is not in buckets until another row comes.
+    last: Option<(usize, Vec<f64>)>,
+    selected: Vec<Vec<f64>>,
+}
+
+impl Downsample {
+    pub fn new(arg: &str) -> Option<Downsample> {
+        match arg {
+            "lttb" => Some(Downsample::Lttb),
+            "minmax" => Some(Downsample::MinMax),
+            _ => None,
+        }
+    }
+    /// indices of points kept, in ascending order.
+    /// all indices are returned if `xs.len() <= threshold`.
+    pub fn select(&self, xs: &[f64], ys: &[f64], threshold: usize) -> Vec<usize> {
+        if xs.len() <= threshold || threshold < 3 {
+            return (0..xs.len()).collect();
+        }
+        let mut reducer = Reducer::new(self, xs.len(), threshold);
+        for (i, (&x, &y)) in xs.iter().zip(ys).enumerate() {
+            reducer.push(i, vec![x, y, i as f64]);
+        }
+        reducer.finish().iter().map(|row| row[2] as usize).collect()
+    }
+}
+impl Reducer {
+    /// reducer of `rows` rows (indices 0 to rows - 1) into at most `threshold` (>= 3) rows.
+    pub fn new(method: &Downsample, rows: usize, threshold: usize) -> Reducer {
+        // min-max takes two rows from each bucket, so it needs room for a bucket
+        let method = if threshold < 4 { Downsample::Lttb } else { method.clone() };
+        let buckets = match method {
+            Downsample::Lttb => threshold - 2,
+            Downsample::MinMax => (threshold - 2) / 2,
+        };
+        Reducer {
+            method,
+            buckets,
+            bucket_size: rows.saturating_sub(2).max(1) as f64 / buckets as f64,
+            pending: Vec::new(),
+            last: None,
+            selected: Vec::new(),
+        }
+    }
+    /// `row` at `index`. indices must be ascending, but may skip rows. (ex. missing values)
+    pub fn push(&mut self, index: usize, row: Vec<f64>) {
+        if self.selected.is_empty() {
+            self.selected.push(row);
+            return;
+        }
+        let (previous_index, previous) = match self.last.replace((index, row)) {
+            Some(last) => last,
+            None => return,
+        };
+        let bucket = self.bucket(previous_index.saturating_sub(1));
+        match self.pending.last_mut() {
+            Some(&mut (b, ref mut rows)) if b >= bucket => rows.push(previous),
+            _ => self.pending.push((bucket, vec![previous])),
+        }
+        if self.pending.len() > 2 {
+            let (_, rows) = self.pending.remove(0);
+            let next = average(&self.pending[0].1);
+            self.reduce(rows, next);
+        }
+    }
+    /// rows kept, in the order pushed.
+    pub fn finish(mut self) -> Vec<Vec<f64>> {
+        let last = self.last.take().map(|(_, row)| row);
+        while !self.pending.is_empty() {
+            let (_, rows) = self.pending.remove(0);
+            let next = self.pending
+                .first()
+                .map(|p| average(&p.1))
+                .or_else(|| last.as_ref().map(|l| (l[0], l[1])))
+                .unwrap_or((rows[0][0], rows[0][1]));
+            self.reduce(rows, next);
+        }
+        self.selected.extend(last);
+        self.selected
+    }
+    /// bucket of the row `offset` rows after the first row.
+    fn bucket(&self, offset: usize) -> usize {
+        // same bounds as `floor(bucket * bucket_size)` of LTTB
+        let mut bucket = ((offset as f64 / self.bucket_size) as usize).min(self.buckets - 1);
+        while bucket + 1 < self.buckets &&
+              ((bucket + 1) as f64 * self.bucket_size) as usize <= offset {
+            bucket += 1;
+        }
+        while bucket > 0 && (bucket as f64 * self.bucket_size) as usize > offset {
+            bucket -= 1;
+        }
+        bucket
+    }
+    fn reduce(&mut self, rows: Vec<Vec<f64>>, next: (f64, f64)) {
+        match self.method {
+            Downsample::Lttb => {
+                // row making the largest triangle with the last kept row and the next bucket
+                let (ax, ay) = self.selected.last().map(|a| (a[0], a[1])).unwrap();
+                let area = |r: &Vec<f64>| {
+                    ((ax - next.0) * (r[1] - ay) - (ax - r[0]) * (next.1 - ay)).abs()
+                };
+                let picked = rows.iter()
+                    .enumerate()
+                    .fold(0, |max, (i, r)| if area(r) > area(&rows[max]) { i } else { max });
+                self.selected.push(rows[picked].clone());
+            }
+            Downsample::MinMax => {
+                // minimum and maximum, which keeps spikes visible
+                let (mut min, mut max) = (0, 0);
+                for (i, r) in rows.iter().enumerate() {
+                    if r[1] < rows[min][1] {
+                        min = i;
+                    }
+                    if r[1] > rows[max][1] {
+                        max = i;
+                    }
+                }
+                self.selected.push(rows[min.min(max)].clone());
+                if min != max {
+                    self.selected.push(rows[min.max(max)].clone());
+                }
+            }
+        }
+    }
+}
+
+/// average (x, y) of rows.
+fn average(rows: &[Vec<f64>]) -> (f64, f64) {
+    let len = rows.len() as f64;
+    (rows.iter().map(|r| r[0]).sum::<f64>() / len, rows.iter().map(|r| r[1]).sum::<f64>() / len)
+}
+
+#[test]
+fn lttb_test() {
+    let xs = (0..100).map(|x| x as f64).collect::<Vec<_>>();
+    let ys = xs.iter().map(|&x| if x == 42.0 { 100.0 } else { 0.0 }).collect::<Vec<_>>();
+    let selected = Downsample::Lttb.select(&xs, &ys, 10);
+    assert_eq!(selected.len(), 10);
+    assert_eq!(selected[0], 0);
+    assert_eq!(selected[9], 99);
+    assert!(selected.contains(&42));
+    assert!(selected.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(Downsample::Lttb.select(&xs[..5], &ys[..5], 10), vec![0, 1, 2, 3, 4]);
+}
+#[test]
+fn min_max_test() {
+    let xs = (0..8).map(|x| x as f64).collect::<Vec<_>>();
+    let ys = vec![1.0, 5.0, 2.0, 0.0, 3.0, 3.0, 9.0, -1.0];
+    assert_eq!(Downsample::MinMax.select(&xs, &ys, 4), vec![0, 3, 6, 7]);
+    // first and last points are kept though they are not extremes, not to shrink x range
+    let xs = (0..100).map(|x| x as f64).collect::<Vec<_>>();
+    let ys = (0..100)
+        .map(|x| if x == 0 || x == 99 { 5.0 } else { (x % 2 * 10) as f64 })
+        .collect::<Vec<_>>();
+    let selected = Downsample::MinMax.select(&xs, &ys, 10);
+    assert!(selected.len() <= 10);
+    assert_eq!((selected[0], selected[selected.len() - 1]), (0, 99));
+    assert!(selected.windows(2).all(|w| w[0] < w[1]));
+}
+#[test]
+fn reducer_test() {
+    // rows with gaps of indices, as rows with missing values are not pushed
+    let mut reducer = Reducer::new(&Downsample::MinMax, 10, 4);
+    for &i in &[0, 2, 3, 5, 8, 9] {
+        reducer.push(i, vec![i as f64, if i == 5 { 10.0 } else { 1.0 }]);
+    }
+    assert_eq!(reducer.finish(),
+               vec![vec![0.0, 1.0], vec![2.0, 1.0], vec![5.0, 10.0], vec![9.0, 1.0]]);
+}
@@ -0,0 +1,591 @@
+/// axis of a series. column number, column name, or an expression over columns.
+/// (ex. `1`, `latency`, `($2/1000)`, `(log(col(3)))`, `($latency-$base)`)
+/// column 0 (`0`, `$0`) is the index of data row, as in gnuplot.
+/// `@N` suffix refers to a column of N-th input file joined by `--join`. (ex. `($ms/$ms@2)`)
+#[derive(Debug,Clone,PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Column(u32),
+    Named(String),
+    Input(u32, Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+}
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Function {
+    Abs,
+    Ceil,
+    Floor,
+    Int,
+    Sqrt,
+    Exp,
+    Log,
+    Log10,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+}
+
+const FUNCTIONS: [(&str, Function, usize); 15] = [("abs", Function::Abs, 1),
+                                                  ("ceil", Function::Ceil, 1),
+                                                  ("floor", Function::Floor, 1),
+                                                  ("int", Function::Int, 1),
+                                                  ("sqrt", Function::Sqrt, 1),
+                                                  ("exp", Function::Exp, 1),
+                                                  ("log", Function::Log, 1),
+                                                  ("log10", Function::Log10, 1),
+                                                  ("sin", Function::Sin, 1),
+                                                  ("cos", Function::Cos, 1),
+                                                  ("tan", Function::Tan, 1),
+                                                  ("asin", Function::Asin, 1),
+                                                  ("acos", Function::Acos, 1),
+                                                  ("atan", Function::Atan, 1),
+                                                  ("atan2", Function::Atan2, 2)];
+
+impl From<u32> for Expr {
+    fn from(column: u32) -> Expr {
+        Expr::Column(column)
+    }
+}
+impl BinaryOp {
+    fn symbol(&self) -> &'static str {
+        match *self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::Pow => "**",
+        }
+    }
+}
+impl Function {
+    fn name(&self) -> &'static str {
+        FUNCTIONS.iter().find(|&&(_, f, _)| f == *self).unwrap().0
+    }
+    fn apply(&self, args: &[f64]) -> f64 {
+        let x = args[0];
+        match *self {
+            Function::Abs => x.abs(),
+            Function::Ceil => x.ceil(),
+            Function::Floor => x.floor(),
+            Function::Int => x.trunc(),
+            Function::Sqrt => x.sqrt(),
+            Function::Exp => x.exp(),
+            Function::Log => x.ln(),
+            Function::Log10 => x.log10(),
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Asin => x.asin(),
+            Function::Acos => x.acos(),
+            Function::Atan => x.atan(),
+            Function::Atan2 => x.atan2(args[1]),
+        }
+    }
+}
+
+impl Expr {
+    /// parse an axis. bare number or name is a column, otherwise expression must be parenthesized.
+    pub fn new(arg: &str) -> Result<Expr, String> {
+        let arg = arg.trim();
+        if arg.is_empty() {
+            return Err(String::from("axis is empty."));
+        }
+        if arg.chars().all(|c| c.is_ascii_digit()) {
+            return arg.parse::<u32>().map(Expr::Column).map_err(|e| e.to_string());
+        }
+        if is_identifier(arg) {
+            return Ok(Expr::Named(arg.to_string()));
+        }
+        if let Some(at) = arg.rfind('@') {
+            let (column, input) = (&arg[..at], &arg[at + 1..]);
+            if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit()) &&
+               !column.starts_with('(') {
+                let input = input.parse::<u32>().map_err(|e| e.to_string())?;
+                return Ok(Expr::Input(input, Box::new(Expr::new(column)?)));
+            }
+        }
+        if !arg.starts_with('(') {
+            return Err(format!("expression must be parenthesized: {}", arg));
+        }
+        let mut parser = Parser {
+            input: arg,
+            position: 0,
+        };
+        let expr = parser.primary()?;
+        parser.skip_whitespace();
+        if parser.position < parser.input.len() {
+            return Err(format!("unexpected \"{}\" in {}", &arg[parser.position..], arg));
+        }
+        Ok(expr)
+    }
+    /// form in gnuplot's `using`. (`2`, `($2/1000.0)`)
+    pub fn to_script(&self) -> String {
+        match *self {
+            Expr::Column(n) => n.to_string(),
+            _ => format!("({})", self.to_expression()),
+        }
+    }
+    /// form as gnuplot expression. (`$2`, `$2/1000.0`)
+    pub fn to_expression(&self) -> String {
+        match *self {
+            Expr::Number(v) => format!("{:?}", v),
+            Expr::Column(n) => format!("${}", n),
+            Expr::Named(ref name) => format!("column(\"{}\")", name),
+            Expr::Input(n, ref e) => format!("{}@{}", e.to_expression(), n),
+            Expr::Negate(ref e) => format!("-{}", e.operand()),
+            Expr::Binary(op, ref l, ref r) => {
+                format!("{}{}{}", l.operand(), op.symbol(), r.operand())
+            }
+            Expr::Call(f, ref args) => {
+                format!("{}({})",
+                        f.name(),
+                        args.iter().map(|a| a.to_expression()).collect::<Vec<_>>().join(","))
+            }
+        }
+    }
+    /// expression, parenthesized unless it is a single value.
+    pub fn operand(&self) -> String {
+        match *self {
+            Expr::Negate(_) | Expr::Binary(..) => format!("({})", self.to_expression()),
+            _ => self.to_expression(),
+        }
+    }
+    /// column number when this axis is a bare column.
+    pub fn column(&self) -> Option<u32> {
+        match *self {
+            Expr::Column(n) => Some(n),
+            _ => None,
+        }
+    }
+    pub fn has_named(&self) -> bool {
+        match *self {
+            Expr::Named(_) => true,
+            Expr::Negate(ref e) => e.has_named(),
+            Expr::Binary(_, ref l, ref r) => l.has_named() || r.has_named(),
+            Expr::Call(_, ref args) => args.iter().any(|a| a.has_named()),
+            _ => false,
+        }
+    }
+    pub fn has_input(&self) -> bool {
+        match *self {
+            Expr::Input(..) => true,
+            Expr::Negate(ref e) => e.has_input(),
+            Expr::Binary(_, ref l, ref r) => l.has_input() || r.has_input(),
+            Expr::Call(_, ref args) => args.iter().any(|a| a.has_input()),
+            _ => false,
+        }
+    }
+    /// column numbers referred in this expression, except the row index `$0`.
+    pub fn columns(&self) -> Vec<u32> {
+        match *self {
+            Expr::Column(0) | Expr::Number(_) | Expr::Named(_) => Vec::new(),
+            Expr::Column(n) => vec![n],
+            Expr::Input(_, ref e) | Expr::Negate(ref e) => e.columns(),
+            Expr::Binary(_, ref l, ref r) => {
+                let mut columns = l.columns();
+                columns.extend(r.columns());
+                columns
+            }
+            Expr::Call(_, ref args) => args.iter().flat_map(|a| a.columns()).collect(),
+        }
+    }
+    /// replace columns of input files by column numbers in joined file.
+    /// `column` maps (N-th input, column or name) to a column number. columns without `@N` are
+    /// of the first input. row index `$0` is of the joined file whichever input it refers.
+    pub fn resolve_inputs<F>(&self, column: &F) -> Result<Expr, String>
+        where F: Fn(u32, &Expr) -> Result<u32, String>
+    {
+        Ok(match *self {
+            Expr::Column(0) => Expr::Column(0),
+            Expr::Input(_, ref e) if **e == Expr::Column(0) => Expr::Column(0),
+            Expr::Column(_) | Expr::Named(_) => Expr::Column(column(1, self)?),
+            Expr::Input(n, ref e) => Expr::Column(column(n, e)?),
+            Expr::Negate(ref e) => Expr::Negate(Box::new(e.resolve_inputs(column)?)),
+            Expr::Binary(op, ref l, ref r) => {
+                Expr::Binary(op,
+                             Box::new(l.resolve_inputs(column)?),
+                             Box::new(r.resolve_inputs(column)?))
+            }
+            Expr::Call(f, ref args) => {
+                Expr::Call(f,
+                           args.iter()
+                               .map(|a| a.resolve_inputs(column))
+                               .collect::<Result<_, _>>()?)
+            }
+            Expr::Number(v) => Expr::Number(v),
+        })
+    }
+    /// replace column names by column numbers in `header`.
+    pub fn resolve(&self, header: &[String]) -> Result<Expr, String> {
+        Ok(match *self {
+            Expr::Named(ref name) => {
+                match header.iter().position(|h| h.trim() == name) {
+                    Some(i) => Expr::Column(i as u32 + 1),
+                    None => return Err(format!("column \"{}\" is not found in header.", name)),
+                }
+            }
+            Expr::Negate(ref e) => Expr::Negate(Box::new(e.resolve(header)?)),
+            Expr::Binary(op, ref l, ref r) => {
+                Expr::Binary(op, Box::new(l.resolve(header)?), Box::new(r.resolve(header)?))
+            }
+            Expr::Call(f, ref args) => {
+                Expr::Call(f, args.iter().map(|a| a.resolve(header)).collect::<Result<_, _>>()?)
+            }
+            ref e => e.clone(),
+        })
+    }
+    /// evaluate with column values. `None` if a column is missing or not a number.
+    pub fn eval<F>(&self, column: &F) -> Option<f64>
+        where F: Fn(u32) -> Option<f64>
+    {
+        match *self {
+            Expr::Number(v) => Some(v),
+            Expr::Column(n) => column(n),
+            Expr::Named(_) | Expr::Input(..) => None,
+            Expr::Negate(ref e) => e.eval(column).map(|v| -v),
+            Expr::Binary(op, ref l, ref r) => {
+                let (l, r) = (l.eval(column)?, r.eval(column)?);
+                let v = match op {
+                    BinaryOp::Add => l + r,
+                    BinaryOp::Sub => l - r,
+                    BinaryOp::Mul => l * r,
+                    BinaryOp::Div => l / r,
+                    BinaryOp::Mod => l % r,
+                    BinaryOp::Pow => l.powf(r),
+                };
+                if v.is_finite() { Some(v) } else { None }
+            }
+            Expr::Call(f, ref args) => {
+                let args = args.iter().map(|a| a.eval(column)).collect::<Option<Vec<_>>>()?;
+                let v = f.apply(&args);
+                if v.is_finite() { Some(v) } else { None }
+            }
+        }
+    }
+}
+
+/// parse `x:y[:y_error]`.
+pub fn parse_axes(arg: &str) -> Result<Vec<Expr>, String> {
+    let axes = split_top_level(arg, ':')
+        .iter()
+        .map(|a| Expr::new(a))
+        .collect::<Result<Vec<_>, _>>()?;
+    if axes.len() == 2 || axes.len() == 3 {
+        Ok(axes)
+    } else {
+        Err(format!("axes must be x:y or x:y:y_error: {}", arg))
+    }
+}
+/// split by `separator` outside of parentheses and quotes.
+pub fn split_top_level(arg: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut part = String::new();
+    let (mut depth, mut quoted) = (0i32, false);
+    for c in arg.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            _ if c == separator && depth == 0 && !quoted => {
+                parts.push(part);
+                part = String::new();
+                continue;
+            }
+            _ => (),
+        }
+        part.push(c);
+    }
+    parts.push(part);
+    parts
+}
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_') &&
+    s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+/// recursive descent parser of expressions. `position` is a byte offset at a char boundary.
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.position..].chars().next()
+    }
+    fn eat(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        if self.input[self.position..].starts_with(s) {
+            self.position += s.len();
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(format!("\"{}\" is expected at {}", s, self.rest()))
+        }
+    }
+    fn rest(&self) -> String {
+        let rest = &self.input[self.position..];
+        if rest.is_empty() { "end".to_string() } else { format!("\"{}\"", rest) }
+    }
+    fn take_while<F: Fn(char) -> bool>(&mut self, f: F) -> String {
+        let start = self.position;
+        self.position = self.input[start..]
+            .char_indices()
+            .find(|&(_, c)| !f(c))
+            .map_or(self.input.len(), |(i, _)| start + i);
+        self.input[start..self.position].to_string()
+    }
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = if self.eat("+") {
+                BinaryOp::Add
+            } else if self.eat("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+    // term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let is_mul = self.peek() == Some('*') &&
+                         !self.input[self.position..].starts_with("**");
+            let op = if is_mul {
+                self.position += 1;
+                BinaryOp::Mul
+            } else if self.eat("/") {
+                BinaryOp::Div
+            } else if self.eat("%") {
+                BinaryOp::Mod
+            } else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+    // unary := '-' unary | '+' unary | power
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            Ok(Expr::Negate(Box::new(self.unary()?)))
+        } else if self.eat("+") {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+    // power := primary ('**' unary)?
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.primary()?;
+        if self.eat("**") {
+            Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(self.unary()?)))
+        } else {
+            Ok(base)
+        }
+    }
+    // primary := number | '$' (number | name) | function '(' args ')' | '(' expression ')'
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                let e = self.expression()?;
+                self.expect(")")?;
+                Ok(e)
+            }
+            Some('$') => {
+                self.position += 1;
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_' || c == '.');
+                let column = if name.is_empty() {
+                    return Err(format!("column is expected at {}", self.rest()));
+                } else if name.chars().all(|c| c.is_ascii_digit()) {
+                    name.parse::<u32>().map(Expr::Column).map_err(|e| e.to_string())?
+                } else {
+                    Expr::Named(name)
+                };
+                if self.eat("@") {
+                    let input = self.take_while(|c| c.is_ascii_digit());
+                    let input = input.parse::<u32>()
+                        .map_err(|_| format!("input number is expected at {}", self.rest()))?;
+                    Ok(Expr::Input(input, Box::new(column)))
+                } else {
+                    Ok(column)
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                if self.peek() == Some('e') || self.peek() == Some('E') {
+                    let exponent_start = self.position;
+                    self.position += 1;
+                    let sign = if self.eat("-") { "-" } else { "" };
+                    let exponent = self.take_while(|c| c.is_ascii_digit());
+                    if exponent.is_empty() {
+                        self.position = exponent_start;
+                    } else {
+                        number = format!("{}e{}{}", number, sign, exponent);
+                    }
+                }
+                number.parse::<f64>()
+                    .map(Expr::Number)
+                    .map_err(|_| format!("invalid number \"{}\"", number))
+            }
+            Some(c) if c.is_alphabetic() => {
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                self.expect("(")?;
+                if name == "col" || name == "column" {
+                    let e = if self.eat("\"") {
+                        let column = self.take_while(|c| c != '"');
+                        self.expect("\"")?;
+                        Expr::Named(column)
+                    } else {
+                        let column = self.take_while(|c| c.is_ascii_digit());
+                        if column.is_empty() {
+                            let column = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                            if column.is_empty() {
+                                return Err(format!("column is expected at {}", self.rest()));
+                            }
+                            Expr::Named(column)
+                        } else {
+                            Expr::Column(column.parse::<u32>().map_err(|e| e.to_string())?)
+                        }
+                    };
+                    self.expect(")")?;
+                    return Ok(e);
+                }
+                let &(_, function, arity) = FUNCTIONS.iter()
+                    .find(|&&(n, _, _)| n == name)
+                    .ok_or(format!("unknown function \"{}\"", name))?;
+                let mut args = vec![self.expression()?];
+                while self.eat(",") {
+                    args.push(self.expression()?);
+                }
+                self.expect(")")?;
+                if args.len() != arity {
+                    return Err(format!("{} takes {} argument(s)", name, arity));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            _ => Err(format!("value is expected at {}", self.rest())),
+        }
+    }
+}
+
+#[test]
+fn expr_new_test() {
+    assert_eq!(Expr::new("2"), Ok(Expr::Column(2)));
+    assert_eq!(Expr::new("latency"), Ok(Expr::Named("latency".to_string())));
+    assert_eq!(Expr::new("($2/1000)"),
+               Ok(Expr::Binary(BinaryOp::Div,
+                               Box::new(Expr::Column(2)),
+                               Box::new(Expr::Number(1000.0)))));
+    assert_eq!(Expr::new("(log($3))"),
+               Ok(Expr::Call(Function::Log, vec![Expr::Column(3)])));
+    assert_eq!(Expr::new("(col(3)-column(\"base\"))"),
+               Ok(Expr::Binary(BinaryOp::Sub,
+                               Box::new(Expr::Column(3)),
+                               Box::new(Expr::Named("base".to_string())))));
+    assert_eq!(Expr::new("($é/$0)"),
+               Ok(Expr::Binary(BinaryOp::Div,
+                               Box::new(Expr::Named("é".to_string())),
+                               Box::new(Expr::Column(0)))));
+    assert_eq!(Expr::new("(col(遅延)*2)").map(|e| e.has_named()), Ok(true));
+    assert!(Expr::new("($2 ÷ 2)").is_err());
+    assert!(Expr::new("$2/1000").is_err());
+    assert!(Expr::new("($2/)").is_err());
+    assert!(Expr::new("(foo($2))").is_err());
+    assert!(Expr::new("(atan2($1))").is_err());
+    assert!(Expr::new("(($2)").is_err());
+    assert!(Expr::new("").is_err());
+}
+#[test]
+fn expr_to_script_test() {
+    let script = |s: &str| Expr::new(s).unwrap().to_script();
+    assert_eq!(script("3"), "3".to_string());
+    assert_eq!(script("($2/1000)"), "($2/1000.0)".to_string());
+    assert_eq!(script("(($2 - $3) * 1e-3)"), "(($2-$3)*0.001)".to_string());
+    assert_eq!(script("(-$2**2)"), "(-($2**2.0))".to_string());
+    assert_eq!(script("(atan2($2, $3))"), "(atan2($2,$3))".to_string());
+    assert_eq!(script("($latency)"), "(column(\"latency\"))".to_string());
+}
+#[test]
+fn expr_eval_test() {
+    let row = [4.0, 9.0, 2.0];
+    let column = |n: u32| row.get(n as usize - 1).cloned();
+    let eval = |s: &str| Expr::new(s).unwrap().eval(&column);
+    assert_eq!(eval("2"), Some(9.0));
+    assert_eq!(eval("(sqrt($2)+col(1)/2)"), Some(5.0));
+    assert_eq!(eval("(2**$3)"), Some(4.0));
+    assert_eq!(eval("(-$1 - -1)"), Some(-3.0));
+    assert_eq!(eval("($4)"), None);
+    assert_eq!(eval("(log(-$1))"), None);
+}
+#[test]
+fn expr_resolve_test() {
+    let header = vec!["time".to_string(), " latency".to_string()];
+    let expr = Expr::new("($latency/1000)").unwrap();
+    assert!(expr.has_named());
+    assert_eq!(expr.resolve(&header).unwrap().to_script(),
+               "($2/1000.0)".to_string());
+    assert!(Expr::new("(col(missing))").unwrap().resolve(&header).is_err());
+    assert!(!Expr::new("($2)").unwrap().has_named());
+}
+#[test]
+fn expr_resolve_inputs_test() {
+    assert_eq!(Expr::new("ms@2"),
+               Ok(Expr::Input(2, Box::new(Expr::Named("ms".to_string())))));
+    let expr = Expr::new("($2/$2@2-$0)").unwrap();
+    assert!(expr.has_input());
+    let offsets = [1, 4];
+    let column = |input: u32, e: &Expr| {
+        e.column().map(|c| offsets[input as usize - 1] + c).ok_or_else(|| "named".to_string())
+    };
+    assert_eq!(expr.resolve_inputs(&column).unwrap().to_script(),
+               "(($3/$6)-$0)".to_string());
+    assert_eq!(Expr::new("($2/$0@2)").unwrap().resolve_inputs(&column).unwrap().to_script(),
+               "($3/$0)".to_string());
+    assert!(Expr::new("($ms@2)").unwrap().resolve_inputs(&column).is_err());
+    assert!(Expr::new("($2@)").is_err());
+}
+#[test]
+fn parse_axes_test() {
+    assert_eq!(parse_axes("1:($2/1000)").unwrap().len(), 2);
+    assert_eq!(parse_axes("time:(atan2($2,$3)):4").unwrap().len(), 3);
+    // row index as x
+    let axes = parse_axes("0:($2*1000)").unwrap();
+    assert_eq!(axes[0].to_script(), "0".to_string());
+    assert_eq!(axes[1].columns(), vec![2]);
+    assert!(axes[0].columns().is_empty());
+    assert!(parse_axes("1").is_err());
+    assert!(parse_axes("1:2:").is_err());
+    assert_eq!(split_top_level("1:(col(\"a,b\")),3:4", ','),
+               vec!["1:(col(\"a,b\"))", "3:4"]);
+}
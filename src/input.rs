@@ -0,0 +1,592 @@
+#[cfg(feature = "arrow")]
+use arrow_array::RecordBatch;
+#[cfg(feature = "arrow")]
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+#[cfg(feature = "arrow")]
+use arrow_ipc::reader::{FileReader, StreamReader};
+use data::Delimiter;
+use flate2::read::MultiGzDecoder;
+use mktemp::Temp;
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+#[cfg(feature = "sqlite")]
+use rusqlite::{Connection, OpenFlags};
+#[cfg(feature = "sqlite")]
+use rusqlite::types::ValueRef;
+use serde_json::Value;
+use std::fs::File;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path;
+use xz2::read::XzDecoder;
+use zip::ZipArchive;
+use zstd::stream::read::Decoder as ZstdDecoder;
+
+/// element type and number of columns of raw little-endian binary input.
+#[derive(Debug,Clone,PartialEq)]
+pub enum BinaryFormat {
+    F32(usize),
+    F64(usize),
+}
+
+/// extensions of raw binary inputs read with `Conversion::binary`.
+pub const BINARY_EXTENSIONS: [&str; 2] = [".bin", ".raw"];
+
+/// how inputs other than delimited text are read.
+#[derive(Debug,Clone)]
+pub struct Conversion {
+    /// format of raw binary inputs, which are `.bin` or `.raw` files.
+    pub binary: Option<BinaryFormat>,
+    /// query run against SQLite database inputs.
+    pub query: Option<String>,
+    /// delimiter of converted files.
+    pub delimiter: Delimiter,
+}
+
+impl BinaryFormat {
+    /// parse `f32`, `f64`, `f32:N` or `f64:N`. (N columns)
+    pub fn new(arg: &str) -> Option<BinaryFormat> {
+        let mut parts = arg.splitn(2, ':');
+        let element = parts.next().unwrap_or("");
+        let columns = match parts.next() {
+            Some(n) => n.parse::<usize>().ok().filter(|&n| n > 0)?,
+            None => 1,
+        };
+        match element {
+            "f32" => Some(BinaryFormat::F32(columns)),
+            "f64" => Some(BinaryFormat::F64(columns)),
+            _ => None,
+        }
+    }
+    fn read<R: Read>(&self, mut reader: R) -> io::Result<Vec<Vec<f64>>> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let (size, columns) = match *self {
+            BinaryFormat::F32(columns) => (4, columns),
+            BinaryFormat::F64(columns) => (8, columns),
+        };
+        let values = bytes.chunks(size)
+            .filter(|chunk| chunk.len() == size)
+            .map(|chunk| element_value(if size == 4 { "<f4" } else { "<f8" }, chunk))
+            .collect::<Vec<_>>();
+        Ok(to_columns(&values, values.len() / columns, columns, false))
+    }
+}
+
+/// convert a compressed, JSON, Arrow, Parquet, SQLite, NumPy or raw binary input into a delimited
+/// text file in `directory`.
+/// `None` if the file is plain text.
+pub fn convert(file: &str,
+               conversion: &Conversion,
+               directory: &path::Path)
+               -> io::Result<Option<Temp>> {
+    let (name, reader): (&str, Box<dyn Read>) = {
+        let opened = BufReader::new(File::open(file)?);
+        if let Some(name) = file.strip_suffix(".gz") {
+            (name, Box::new(MultiGzDecoder::new(opened)))
+        } else if let Some(name) = file.strip_suffix(".zst") {
+            (name, Box::new(ZstdDecoder::new(opened)?))
+        } else if let Some(name) = file.strip_suffix(".xz") {
+            (name, Box::new(XzDecoder::new(opened)))
+        } else {
+            (file, Box::new(opened))
+        }
+    };
+    let (header, rows) = if [".db", ".sqlite", ".sqlite3"].iter().any(|e| name.ends_with(e)) {
+        let query = conversion.query
+            .as_ref()
+            .ok_or_else(|| invalid_data("--query must be given for SQLite database.".to_string()))?;
+        // compressed database is decompressed to a file, which SQLite opens
+        let (names, rows) = if name.len() < file.len() {
+            let decompressed = copy_text(reader, directory)?;
+            read_sqlite(decompressed.as_ref().to_str().unwrap(), query.as_str())?
+        } else {
+            read_sqlite(file, query.as_str())?
+        };
+        (Some(names), rows)
+    } else if name.ends_with(".parquet") {
+        // parquet is read with random access, so compressed one is decompressed to a file first
+        let (names, rows) = if name.len() < file.len() {
+            let decompressed = copy_text(reader, directory)?;
+            read_parquet(File::open(decompressed.as_ref())?)?
+        } else {
+            read_parquet(File::open(file)?)?
+        };
+        (Some(names), rows)
+    } else if [".arrow", ".arrows", ".feather"].iter().any(|e| name.ends_with(e)) {
+        let (names, rows) = read_arrow_ipc(reader)?;
+        (Some(names), rows)
+    } else if name.ends_with(".jsonl") || name.ends_with(".ndjson") {
+        let (names, rows) = read_json(reader, true)?;
+        (Some(names), rows)
+    } else if name.ends_with(".json") {
+        let (names, rows) = read_json(reader, false)?;
+        (Some(names), rows)
+    } else if name.ends_with(".npy") {
+        (None, to_rows(&read_npy(reader)?)?)
+    } else if name.ends_with(".npz") {
+        let (names, columns) = read_npz(reader)?;
+        (Some(names), to_rows(&columns)?)
+    } else if let Some(binary) = conversion.binary
+        .as_ref()
+        .filter(|_| BINARY_EXTENSIONS.iter().any(|e| name.ends_with(e))) {
+        (None, to_rows(&binary.read(reader)?)?)
+    } else if name.len() < file.len() {
+        return copy_text(reader, directory).map(Some);
+    } else {
+        return Ok(None);
+    };
+    write_table(directory, &conversion.delimiter, header, &rows).map(Some)
+}
+
+fn copy_text<R: Read>(mut reader: R, directory: &path::Path) -> io::Result<Temp> {
+    let temp_file = Temp::new_file_in(directory)?;
+    {
+        let mut writer = io::BufWriter::new(File::create(temp_file.as_ref())?);
+        io::copy(&mut reader, &mut writer)?;
+    }
+    Ok(temp_file)
+}
+fn write_table(directory: &path::Path,
+               delimiter: &Delimiter,
+               header: Option<Vec<String>>,
+               rows: &[Vec<String>])
+               -> io::Result<Temp> {
+    let temp_file = Temp::new_file_in(directory)?;
+    {
+        let mut writer = io::BufWriter::new(File::create(temp_file.as_ref())?);
+        for row in header.iter().chain(rows.iter()) {
+            writeln!(writer, "{}", delimiter.join(row))?;
+        }
+    }
+    Ok(temp_file)
+}
+/// rows of `columns`, which must be of the same length.
+fn to_rows(columns: &[Vec<f64>]) -> io::Result<Vec<Vec<String>>> {
+    let rows = columns.first().map(|c| c.len()).unwrap_or(0);
+    if columns.iter().any(|c| c.len() != rows) {
+        let lengths = columns.iter().map(|c| c.len().to_string()).collect::<Vec<_>>();
+        return Err(invalid_data(format!("arrays are of different lengths: {}",
+                                        lengths.join(", "))));
+    }
+    Ok((0..rows).map(|i| columns.iter().map(|c| c[i].to_string()).collect()).collect())
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+#[cfg(not(all(feature = "arrow", feature = "parquet", feature = "sqlite")))]
+fn without_feature(feature: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported,
+                   format!("gnuplotters is built without \"{}\" feature.", feature))
+}
+/// objects in JSON Lines (`lines`), or in a JSON array (or a single object), flattened to rows.
+/// columns are named by dotted path (ex. `latency.p99`, `samples.0`) in order of appearance.
+pub fn read_json<R: Read>(mut reader: R,
+                          lines: bool)
+                          -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let objects = if lines {
+        text.lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(serde_json::from_str::<Value>)
+            .collect::<Result<Vec<_>, _>>()
+    } else {
+        serde_json::from_str::<Value>(text.as_str()).map(|v| match v {
+            Value::Array(objects) => objects,
+            object => vec![object],
+        })
+    }
+    .map_err(|e| invalid_data(e.to_string()))?;
+    let mut names: Vec<String> = Vec::new();
+    let mut records = Vec::new();
+    for object in &objects {
+        let mut fields = Vec::new();
+        flatten("", object, &mut fields);
+        for (name, _) in &fields {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        records.push(fields);
+    }
+    let rows = records.into_iter()
+        .map(|fields| {
+            names.iter()
+                .map(|n| {
+                    fields.iter().find(|f| &f.0 == n).map(|f| f.1.clone()).unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+    Ok((names, rows))
+}
+/// columns of Arrow IPC file (`.arrow`, `.feather` v2) or stream, named by fields.
+#[cfg(feature = "arrow")]
+pub fn read_arrow_ipc<R: Read>(mut reader: R) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let batches = match FileReader::try_new(io::Cursor::new(&bytes), None) {
+        Ok(file_reader) => file_reader.collect::<Result<Vec<_>, _>>(),
+        Err(_) => {
+            StreamReader::try_new(io::Cursor::new(&bytes), None)
+                .and_then(|stream_reader| stream_reader.collect::<Result<Vec<_>, _>>())
+        }
+    };
+    batches.map_err(|e| invalid_data(e.to_string())).and_then(|b| batch_rows(&b))
+}
+#[cfg(not(feature = "arrow"))]
+pub fn read_arrow_ipc<R: Read>(_: R) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    Err(without_feature("arrow"))
+}
+/// columns of Parquet file, named by fields.
+#[cfg(feature = "parquet")]
+pub fn read_parquet(file: File) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.build())
+        .map_err(|e| invalid_data(e.to_string()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| invalid_data(e.to_string()))
+        .and_then(|b| batch_rows(&b))
+}
+#[cfg(not(feature = "parquet"))]
+pub fn read_parquet(_: File) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    Err(without_feature("parquet"))
+}
+/// result of `query` against SQLite database, named by result columns.
+#[cfg(feature = "sqlite")]
+pub fn read_sqlite(file: &str, query: &str) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let sqlite_error = |e: rusqlite::Error| invalid_data(e.to_string());
+    let connection = Connection::open_with_flags(file, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(sqlite_error)?;
+    let mut statement = connection.prepare(query).map_err(sqlite_error)?;
+    let names = statement.column_names().iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let rows = statement.query_map([], |row| {
+            (0..names.len())
+                .map(|i| {
+                    row.get_ref(i).map(|value| match value {
+                        ValueRef::Integer(v) => v.to_string(),
+                        ValueRef::Real(v) => v.to_string(),
+                        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+                        ValueRef::Null | ValueRef::Blob(_) => String::new(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(sqlite_error)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(sqlite_error)?;
+    Ok((names, rows))
+}
+#[cfg(not(feature = "sqlite"))]
+pub fn read_sqlite(_: &str, _: &str) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    Err(without_feature("sqlite"))
+}
+/// values of record batches formatted as text. (null is empty, timestamp is ISO 8601)
+#[cfg(feature = "arrow")]
+fn batch_rows(batches: &[RecordBatch]) -> io::Result<(Vec<String>, Vec<Vec<String>>)> {
+    let names = batches.first()
+        .map(|b| b.schema().fields().iter().map(|f| f.name().clone()).collect())
+        .unwrap_or_default();
+    let options = FormatOptions::default().with_null("");
+    let mut rows = Vec::new();
+    for batch in batches {
+        let formatters = batch.columns()
+            .iter()
+            .map(|c| ArrayFormatter::try_new(c.as_ref(), &options))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| invalid_data(e.to_string()))?;
+        for i in 0..batch.num_rows() {
+            rows.push(formatters.iter().map(|f| f.value(i).to_string()).collect());
+        }
+    }
+    Ok((names, rows))
+}
+fn flatten(path: &str, value: &Value, fields: &mut Vec<(String, String)>) {
+    let child = |key: &str| if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    };
+    match *value {
+        Value::Object(ref map) => {
+            for (key, v) in map {
+                flatten(child(key).as_str(), v, fields);
+            }
+        }
+        Value::Array(ref values) => {
+            for (i, v) in values.iter().enumerate() {
+                flatten(child(i.to_string().as_str()).as_str(), v, fields);
+            }
+        }
+        Value::String(ref s) => fields.push((path.to_string(), s.clone())),
+        Value::Number(ref n) => fields.push((path.to_string(), n.to_string())),
+        Value::Bool(b) => fields.push((path.to_string(), if b { "1" } else { "0" }.to_string())),
+        Value::Null => fields.push((path.to_string(), String::new())),
+    }
+}
+/// columns of 1-D or 2-D array in NumPy `.npy` format.
+pub fn read_npy<R: Read>(mut reader: R) -> io::Result<Vec<Vec<f64>>> {
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic[..6] != b"\x93NUMPY" {
+        return Err(invalid_data("not a npy file.".to_string()));
+    }
+    let header_length = if magic[6] == 1 {
+        let mut length = [0u8; 2];
+        reader.read_exact(&mut length)?;
+        u16::from_le_bytes(length) as usize
+    } else {
+        let mut length = [0u8; 4];
+        reader.read_exact(&mut length)?;
+        u32::from_le_bytes(length) as usize
+    };
+    let mut header = vec![0u8; header_length];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8_lossy(&header).into_owned();
+    let descr = header_value(&header, "descr")
+        .map(|d| d.trim_matches(['\'', '"']).to_string())
+        .ok_or_else(|| invalid_data(format!("descr is not found in npy header: {}", header)))?;
+    let fortran_order = header_value(&header, "fortran_order") == Some("True");
+    let shape = header_value(&header, "shape")
+        .map(|s| {
+            s.trim_matches(['(', ')'])
+                .split(',')
+                .filter(|d| !d.trim().is_empty())
+                .map(|d| d.trim().parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .and_then(|s| s.ok())
+        .ok_or_else(|| invalid_data(format!("shape is not found in npy header: {}", header)))?;
+    let (rows, columns) = match shape.len() {
+        0 => (1, 1),
+        1 => (shape[0], 1),
+        2 => (shape[0], shape[1]),
+        n => return Err(invalid_data(format!("{}-dimensional array is not supported.", n))),
+    };
+    let size = element_size(&descr)
+        .ok_or_else(|| invalid_data(format!("dtype {} is not supported.", descr)))?;
+    let mut bytes = vec![0u8; rows * columns * size];
+    reader.read_exact(&mut bytes)?;
+    let values = bytes.chunks(size).map(|chunk| element_value(&descr, chunk)).collect::<Vec<_>>();
+    Ok(to_columns(&values, rows, columns, fortran_order))
+}
+/// columns of arrays in NumPy `.npz` archive, named by the arrays. (`x`, `y`, `data.1`, ..)
+fn read_npz<R: Read>(mut reader: R) -> io::Result<(Vec<String>, Vec<Vec<f64>>)> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let mut archive = ZipArchive::new(io::Cursor::new(bytes))
+        .map_err(|e| invalid_data(e.to_string()))?;
+    let (mut names, mut columns) = (Vec::new(), Vec::new());
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(|e| invalid_data(e.to_string()))?;
+        let name = entry.name().trim_end_matches(".npy").to_string();
+        let array = read_npy(entry)?;
+        if array.len() == 1 {
+            names.push(name);
+        } else {
+            names.extend((1..array.len() + 1).map(|c| format!("{}.{}", name, c)));
+        }
+        columns.extend(array);
+    }
+    Ok((names, columns))
+}
+/// value of `key` in header dictionary. (ex. `{'descr': '<f8', 'shape': (3, 2), }`)
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find([',', '}'])?
+    };
+    Some(rest[..end].trim())
+}
+fn element_size(descr: &str) -> Option<usize> {
+    match descr.trim_start_matches(['<', '>', '|', '=']) {
+        "f8" | "i8" | "u8" => Some(8),
+        "f4" | "i4" | "u4" => Some(4),
+        "i2" | "u2" => Some(2),
+        "i1" | "u1" | "b1" => Some(1),
+        _ => None,
+    }
+}
+fn element_value(descr: &str, bytes: &[u8]) -> f64 {
+    let mut buffer = [0u8; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    if descr.starts_with('>') {
+        buffer[..bytes.len()].reverse();
+    }
+    match descr.trim_start_matches(['<', '>', '|', '=']) {
+        "f8" => f64::from_le_bytes(buffer),
+        "f4" => f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+        "i8" => i64::from_le_bytes(buffer) as f64,
+        "u8" => u64::from_le_bytes(buffer) as f64,
+        "i4" => i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+        "u4" => u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as f64,
+        "i2" => i16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+        "u2" => u16::from_le_bytes([buffer[0], buffer[1]]) as f64,
+        "i1" => buffer[0] as i8 as f64,
+        _ => buffer[0] as f64,
+    }
+}
+fn to_columns(values: &[f64], rows: usize, columns: usize, fortran_order: bool) -> Vec<Vec<f64>> {
+    (0..columns)
+        .map(|c| {
+            (0..rows)
+                .map(|r| if fortran_order { values[c * rows + r] } else { values[r * columns + c] })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn binary_format_test() {
+    assert_eq!(BinaryFormat::new("f64"), Some(BinaryFormat::F64(1)));
+    assert_eq!(BinaryFormat::new("f32:3"), Some(BinaryFormat::F32(3)));
+    assert_eq!(BinaryFormat::new("f32:0"), None);
+    assert_eq!(BinaryFormat::new("i16"), None);
+    let bytes = [1.0f32, 2.0, 3.0, 4.0].iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<_>>();
+    assert_eq!(BinaryFormat::F32(2).read(io::Cursor::new(bytes)).unwrap(),
+               vec![vec![1.0, 3.0], vec![2.0, 4.0]]);
+}
+#[test]
+fn to_rows_test() {
+    assert_eq!(to_rows(&[vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap(),
+               vec![vec!["1".to_string(), "3".to_string()],
+                    vec!["2".to_string(), "4".to_string()]]);
+    assert_eq!(to_rows(&[vec![1.0, 2.0], vec![3.0]]).map_err(|e| e.kind()),
+               Err(io::ErrorKind::InvalidData));
+}
+#[test]
+fn read_json_test() {
+    let input = "{\"ts\": \"a\", \"latency\": {\"p50\": 1, \"p99\": 2.5}}\n\n\
+                 {\"ts\": \"b\", \"latency\": {\"p99\": 4}, \"ok\": true}\n";
+    let (names, rows) = read_json(io::Cursor::new(input), true).unwrap();
+    assert_eq!(names, vec!["ts", "latency.p50", "latency.p99", "ok"]);
+    assert_eq!(rows,
+               vec![vec!["a", "1", "2.5", ""], vec!["b", "", "4", "1"]]
+                   .into_iter()
+                   .map(|r| r.into_iter().map(|f| f.to_string()).collect::<Vec<_>>())
+                   .collect::<Vec<_>>());
+    let (names, rows) = read_json(io::Cursor::new("[{\"x\": [1, 2]}, {\"x\": [3, 4]}]"), false)
+        .unwrap();
+    assert_eq!(names, vec!["x.0", "x.1"]);
+    assert_eq!(rows[1], vec!["3".to_string(), "4".to_string()]);
+    assert!(read_json(io::Cursor::new("{\"x\": "), false).is_err());
+}
+#[test]
+#[cfg(feature = "parquet")]
+fn read_arrow_test() {
+    use arrow_array::{Float64Array, StringArray};
+    use arrow_ipc::writer::FileWriter;
+    use std::sync::Arc;
+    let batch = RecordBatch::try_from_iter(vec![("name",
+                                                 Arc::new(StringArray::from(vec!["a", "b"])) as
+                                                 Arc<dyn arrow_array::Array>),
+                                                ("ms",
+                                                 Arc::new(Float64Array::from(vec![Some(1.5),
+                                                                                  None])))])
+        .unwrap();
+    let mut bytes = Vec::new();
+    {
+        let mut writer = FileWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+    }
+    let (names, rows) = read_arrow_ipc(io::Cursor::new(bytes)).unwrap();
+    assert_eq!(names, vec!["name", "ms"]);
+    assert_eq!(rows,
+               vec![vec!["a".to_string(), "1.5".to_string()],
+                    vec!["b".to_string(), "".to_string()]]);
+    let parquet_file = Temp::new_file().unwrap();
+    {
+        let file = File::create(parquet_file.as_ref()).unwrap();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(file, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+    }
+    assert_eq!(read_parquet(File::open(parquet_file.as_ref()).unwrap()).unwrap(),
+               (names, rows));
+    // compressed parquet is decompressed before it is read
+    let directory = Temp::new_dir().unwrap();
+    let compressed = directory.as_ref().join("result.parquet.gz");
+    {
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&compressed).unwrap(),
+                                                        flate2::Compression::default());
+        io::copy(&mut File::open(parquet_file.as_ref()).unwrap(), &mut encoder).unwrap();
+        encoder.finish().unwrap();
+    }
+    let conversion = Conversion {
+        binary: None,
+        query: None,
+        delimiter: Delimiter::Char(','),
+    };
+    let converted = convert(compressed.to_str().unwrap(), &conversion, directory.as_ref())
+        .unwrap()
+        .unwrap();
+    let mut text = String::new();
+    File::open(converted.as_ref()).unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "name,ms\na,1.5\nb,\n");
+}
+#[test]
+#[cfg(feature = "sqlite")]
+fn read_sqlite_test() {
+    let database = Temp::new_file().unwrap();
+    let file = database.as_ref().to_str().unwrap();
+    {
+        let connection = Connection::open(file).unwrap();
+        connection.execute_batch("CREATE TABLE runs (n INTEGER, t REAL, note TEXT);
+                                  INSERT INTO runs VALUES (1, 2.0, 'a'), (1, 4.0, NULL), \
+                                  (2, 3.0, 'b');")
+            .unwrap();
+    }
+    let (names, rows) = read_sqlite(file, "SELECT n, avg(t) AS t FROM runs GROUP BY n").unwrap();
+    assert_eq!(names, vec!["n", "t"]);
+    assert_eq!(rows,
+               vec![vec!["1".to_string(), "3".to_string()],
+                    vec!["2".to_string(), "3".to_string()]]);
+    assert!(read_sqlite(file, "SELECT * FROM missing").is_err());
+    // compressed database is decompressed before it is opened
+    let directory = Temp::new_dir().unwrap();
+    let compressed = directory.as_ref().join("results.db.gz");
+    {
+        let mut encoder = flate2::write::GzEncoder::new(File::create(&compressed).unwrap(),
+                                                        flate2::Compression::default());
+        io::copy(&mut File::open(file).unwrap(), &mut encoder).unwrap();
+        encoder.finish().unwrap();
+    }
+    let conversion = Conversion {
+        binary: None,
+        query: Some("SELECT n, t FROM runs WHERE note = 'b'".to_string()),
+        delimiter: Delimiter::Char(','),
+    };
+    let converted = convert(compressed.to_str().unwrap(), &conversion, directory.as_ref())
+        .unwrap()
+        .unwrap();
+    let mut text = String::new();
+    File::open(converted.as_ref()).unwrap().read_to_string(&mut text).unwrap();
+    assert_eq!(text, "n,t\n2,3\n");
+}
+#[test]
+fn read_npy_test() {
+    let npy = |header: &str, data: Vec<u8>| {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend(data);
+        bytes
+    };
+    let data = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let bytes = npy("{'descr': '<f8', 'fortran_order': False, 'shape': (3, 2), }\n", data);
+    assert_eq!(read_npy(io::Cursor::new(bytes)).unwrap(),
+               vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]]);
+    let data = [1i32, -2, 3].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let bytes = npy("{'descr': '<i4', 'fortran_order': False, 'shape': (3,), }\n", data);
+    assert_eq!(read_npy(io::Cursor::new(bytes)).unwrap(),
+               vec![vec![1.0, -2.0, 3.0]]);
+    let bytes = npy("{'descr': '<c16', 'fortran_order': False, 'shape': (1,), }\n", vec![0; 16]);
+    assert!(read_npy(io::Cursor::new(bytes)).is_err());
+}
@@ -0,0 +1,247 @@
+use data;
+use data::{DataFormat, Delimiter};
+use serde_json::{Map, Value};
+use std::io;
+use std::io::prelude::*;
+use std::iter;
+use timefmt::TimeFormat;
+
+/// type of values in a column, inferred from all of its non-missing cells.
+#[derive(Debug,Clone,PartialEq)]
+pub enum ColumnType {
+    Numeric,
+    Time(TimeFormat),
+    Text,
+}
+/// a column of input file. `min` and `max` are (value, cell) of numeric or time columns.
+#[derive(Debug,Clone,PartialEq)]
+pub struct ColumnSummary {
+    pub index: u32,
+    pub name: Option<String>,
+    pub column_type: ColumnType,
+    pub missing: usize,
+    pub min: Option<(f64, String)>,
+    pub max: Option<(f64, String)>,
+}
+/// what `inspect` subcommand reports about an input file.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Summary {
+    pub delimiter: Delimiter,
+    pub header: Vec<String>,
+    pub rows: usize,
+    pub columns: Vec<ColumnSummary>,
+}
+
+/// time formats tried for columns which are not numeric, after the one given by `--xtime`.
+const TIME_FORMATS: [&str; 6] = ["%Y-%m-%dT%H:%M:%S",
+                                 "%Y-%m-%d %H:%M:%S",
+                                 "%Y-%m-%d",
+                                 "%Y/%m/%d",
+                                 "%d/%m/%Y",
+                                 "%H:%M:%S"];
+
+impl ColumnType {
+    fn name(&self) -> &'static str {
+        match *self {
+            ColumnType::Numeric => "numeric",
+            ColumnType::Time(_) => "time",
+            ColumnType::Text => "string",
+        }
+    }
+}
+impl Summary {
+    pub fn to_text(&self, file: &str) -> String {
+        let mut table = vec![vec!["column".to_string(),
+                                  "name".to_string(),
+                                  "type".to_string(),
+                                  "missing".to_string(),
+                                  "min".to_string(),
+                                  "max".to_string()]];
+        for c in &self.columns {
+            let column_type = match c.column_type {
+                ColumnType::Time(ref time) => format!("time ({})", time.timefmt()),
+                ref t => t.name().to_string(),
+            };
+            let cell = |v: &Option<(f64, String)>| {
+                v.as_ref().map(|v| v.1.clone()).unwrap_or_else(|| "-".to_string())
+            };
+            table.push(vec![c.index.to_string(),
+                            c.name.clone().unwrap_or_else(|| "-".to_string()),
+                            column_type,
+                            c.missing.to_string(),
+                            cell(&c.min),
+                            cell(&c.max)]);
+        }
+        let widths = (0..table[0].len())
+            .map(|i| table.iter().map(|r| r[i].chars().count()).max().unwrap_or(0))
+            .collect::<Vec<_>>();
+        let mut text = format!("{}\n  delimiter: {}\n  rows: {}\n",
+                               file,
+                               delimiter_name(&self.delimiter),
+                               self.rows);
+        for row in table {
+            let cells = row.iter()
+                .zip(widths.iter())
+                .map(|(cell, &w)| format!("{:w$}", cell, w = w))
+                .collect::<Vec<_>>();
+            text += format!("  {}\n", cells.join("  ").trim_end()).as_str();
+        }
+        text
+    }
+    pub fn to_json(&self, file: &str) -> Value {
+        let columns = self.columns
+            .iter()
+            .map(|c| {
+                let value = |v: &Option<(f64, String)>| match (v, &c.column_type) {
+                    (&Some((x, _)), &ColumnType::Numeric) => Value::from(x),
+                    (&Some((_, ref cell)), _) => Value::from(cell.clone()),
+                    (&None, _) => Value::Null,
+                };
+                let mut column = Map::new();
+                column.insert("index".to_string(), Value::from(c.index));
+                column.insert("name".to_string(), Value::from(c.name.clone()));
+                column.insert("type".to_string(), Value::from(c.column_type.name()));
+                if let ColumnType::Time(ref time) = c.column_type {
+                    column.insert("time_format".to_string(), Value::from(time.timefmt()));
+                }
+                column.insert("missing".to_string(), Value::from(c.missing));
+                column.insert("min".to_string(), value(&c.min));
+                column.insert("max".to_string(), value(&c.max));
+                Value::Object(column)
+            })
+            .collect::<Vec<_>>();
+        let mut summary = Map::new();
+        summary.insert("file".to_string(), Value::from(file));
+        summary.insert("delimiter".to_string(),
+                       Value::from(delimiter_name(&self.delimiter)));
+        summary.insert("header".to_string(), Value::from(self.header.clone()));
+        summary.insert("rows".to_string(), Value::from(self.rows));
+        summary.insert("columns".to_string(), Value::Array(columns));
+        Value::Object(summary)
+    }
+}
+
+/// summarize data rows of a delimited text.
+/// header is the last skipped row if rows are skipped, otherwise the first row if none of its
+/// fields is a number.
+pub fn summarize<R: BufRead>(reader: R, format: &DataFormat) -> io::Result<Summary> {
+    let mut header = Vec::new();
+    let mut rows = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if number < format.skip_rows {
+            if number + 1 == format.skip_rows {
+                header = format.delimiter.split(line.as_str());
+            }
+        } else if format.is_data_line(number, line.as_str()) && !line.trim().is_empty() {
+            rows.push(format.delimiter.split(line.as_str()));
+        }
+    }
+    if format.skip_rows == 0 &&
+       rows.first().is_some_and(|r| data::is_header_row(r)) {
+        header = rows.remove(0);
+    }
+    let width = rows.iter().chain(iter::once(&header)).map(|r| r.len()).max().unwrap_or(0);
+    let columns = (0..width)
+        .map(|c| {
+            let cells = rows.iter()
+                .filter_map(|r| r.get(c).map(|cell| cell.trim()))
+                .filter(|cell| !cell.is_empty() && format.missing.as_deref() != Some(*cell))
+                .collect::<Vec<_>>();
+            summarize_column(c as u32 + 1,
+                             header.get(c).cloned(),
+                             &cells,
+                             rows.len() - cells.len(),
+                             format.x_time.as_ref())
+        })
+        .collect();
+    Ok(Summary {
+        delimiter: format.delimiter.clone(),
+        header,
+        rows: rows.len(),
+        columns,
+    })
+}
+fn summarize_column(index: u32,
+                    name: Option<String>,
+                    cells: &[&str],
+                    missing: usize,
+                    x_time: Option<&TimeFormat>)
+                    -> ColumnSummary {
+    let parse_all = |parse: &dyn Fn(&str) -> Option<f64>| {
+        cells.iter().map(|cell| parse(cell)).collect::<Option<Vec<_>>>()
+    };
+    let typed = if cells.is_empty() {
+        None
+    } else if let Some(values) = parse_all(&|cell| cell.parse::<f64>().ok()) {
+        Some((ColumnType::Numeric, values))
+    } else {
+        x_time.cloned()
+            .into_iter()
+            .chain(TIME_FORMATS.iter().map(|f| TimeFormat::new(f)))
+            .find_map(|time| {
+                parse_all(&|cell| time.parse(cell)).map(|values| (ColumnType::Time(time), values))
+            })
+    };
+    let (column_type, values) = typed.unwrap_or((ColumnType::Text, Vec::new()));
+    let pick = |better: fn(f64, f64) -> bool| {
+        values.iter()
+            .zip(cells.iter())
+            .fold(None, |picked: Option<(f64, &str)>, (&v, &cell)| match picked {
+                Some((p, _)) if !better(v, p) => picked,
+                _ => Some((v, cell)),
+            })
+            .map(|(v, cell)| (v, cell.to_string()))
+    };
+    ColumnSummary {
+        index,
+        name,
+        column_type,
+        missing,
+        min: pick(|v, p| v < p),
+        max: pick(|v, p| v > p),
+    }
+}
+fn delimiter_name(delimiter: &Delimiter) -> String {
+    match *delimiter {
+        Delimiter::Char(',') => "comma".to_string(),
+        Delimiter::Char('\t') => "tab".to_string(),
+        Delimiter::Char(';') => "semicolon".to_string(),
+        Delimiter::Char(c) => c.to_string(),
+        Delimiter::Whitespace => "whitespace".to_string(),
+    }
+}
+
+#[test]
+fn summarize_test() {
+    let text = "time,value,label,empty\n\
+                2017-03-04,1.5,a,\n\
+                # comment\n\
+                2017-03-02,NA,b,\n\
+                2017-03-05,-2,c,\n";
+    let mut format = DataFormat::new(Delimiter::Char(','));
+    format.missing = Some("NA".to_string());
+    let summary = summarize(io::Cursor::new(text), &format).unwrap();
+    assert_eq!(summary.header, vec!["time", "value", "label", "empty"]);
+    assert_eq!(summary.rows, 3);
+    let time = &summary.columns[0];
+    assert_eq!(time.column_type, ColumnType::Time(TimeFormat::new("%Y-%m-%d")));
+    assert_eq!(time.min.as_ref().map(|m| m.1.as_str()), Some("2017-03-02"));
+    let value = &summary.columns[1];
+    assert_eq!((value.index, value.missing), (2, 1));
+    assert_eq!((value.min.clone(), value.max.clone()),
+               (Some((-2.0, "-2".to_string())), Some((1.5, "1.5".to_string()))));
+    assert_eq!(summary.columns[2].column_type, ColumnType::Text);
+    assert_eq!((summary.columns[3].missing, summary.columns[3].min.clone()), (3, None));
+    let json = summary.to_json("data.csv");
+    assert_eq!(json["columns"][1]["max"], Value::from(1.5));
+    assert_eq!(json["columns"][0]["time_format"], Value::from("%Y-%m-%d"));
+    format.delimiter = Delimiter::Whitespace;
+    format.skip_rows = 1;
+    let summary = summarize(io::Cursor::new("x y\n1 2\n3 4\n"), &format).unwrap();
+    assert_eq!((summary.header.len(), summary.rows), (2, 2));
+    format.skip_rows = 0;
+    let summary = summarize(io::Cursor::new("1 2\n3 4 5\n"), &format).unwrap();
+    assert_eq!((summary.header.len(), summary.rows, summary.columns.len()), (0, 2, 3));
+    assert_eq!(summary.columns[2].missing, 1);
+}
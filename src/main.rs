@@ -1,16 +1,61 @@
 #![allow(dead_code)]
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_cast;
+#[cfg(feature = "arrow")]
+extern crate arrow_ipc;
 #[macro_use]
 extern crate clap;
+extern crate flate2;
 extern crate mktemp;
+#[cfg(feature = "parquet")]
+extern crate parquet;
 extern crate regex;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
+extern crate serde_json;
+extern crate xz2;
+extern crate zip;
+extern crate zstd;
+
+mod aggregate;
+mod annotation;
+mod canvas;
+mod criterion;
+mod data;
+mod downsample;
+mod expr;
+mod input;
+mod inspect;
+mod palette;
+mod theme;
+mod tics;
+mod timefmt;
+mod trend;
 
 use clap::Arg;
 use mktemp::Temp;
 use regex::Regex;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io;
 use std::process::Command;
 use std::path;
+use aggregate::{Aggregate, Spread};
+use annotation::Annotation;
+use canvas::Size;
+use criterion::Estimate;
+use data::{DataFormat, Delimiter};
+use downsample::{Downsample, Reducer};
+use expr::Expr;
+use input::BinaryFormat;
+use palette::Palette;
+use theme::Theme;
+use tics::{AxisTics, Tics};
+use timefmt::TimeFormat;
+use trend::Trend;
 
 #[derive(Debug)]
 struct PlotScript {
@@ -18,20 +63,47 @@ struct PlotScript {
     font: String,
     delimiter: String,
     legend_position: String,
+    key_options: Vec<String>,
+    size: Option<String>,
+    /// `fontscale` and `linewidth` of raster terminal.
+    scale: Option<f64>,
+    size_ratio: Option<String>,
+    grid: bool,
+    border: Option<u32>,
+    key_box: bool,
+    background: Option<Color>,
+    foreground: Option<Color>,
     plot: Vec<Series>,
     x_label: String,
     y_label: String,
+    x_time: Option<TimeFormat>,
+    x_format: Option<String>,
+    y_format: Option<String>,
+    title: Option<String>,
+    x_tics: AxisTics,
+    y_tics: AxisTics,
+    grid_tics: Vec<String>,
+    grid_style: Option<String>,
+    annotations: Vec<String>,
+    pre: Vec<String>,
+    post: Vec<String>,
+    comment: Option<String>,
+    missing: Option<String>,
 }
 #[derive(Debug)]
 struct Series {
     data_file: String,
     title: Option<String>,
-    axes: (u32, u32),
-    y_errorbar: Option<u32>,
+    axes: (Expr, Expr),
+    y_errorbar: Option<Expr>,
+    y_range: Option<(Expr, Expr)>,
     s_type: SeriesType,
     l_size: f32,
     color: Color,
-    l_type: u32,
+    l_type: LineType,
+    smooth: Option<Smooth>,
+    x_time_scale: Option<u32>,
+    skip: usize,
 }
 
 #[derive(Debug,PartialEq,Clone)]
@@ -40,11 +112,34 @@ enum SeriesType {
     Point,
     YERRORBAR,
 }
+/// dash type of lines and point type of points, given by `-l`.
+#[derive(Debug,Clone,PartialEq)]
+struct LineType {
+    dash: Dash,
+    point: u32,
+}
+#[derive(Debug,Clone,PartialEq)]
+enum Dash {
+    Number(u32),
+    /// lengths of dashes and gaps. (ex. `(10,5,2,5)`)
+    Pattern(Vec<u32>),
+}
 #[derive(Debug,Clone,PartialEq)]
 enum Color {
     Name(String),
     Code(String),
 }
+#[derive(Debug,PartialEq,Clone)]
+enum Smooth {
+    Csplines,
+    Acsplines,
+    Bezier,
+    Sbezier,
+    Kdensity,
+    Unique,
+    Frequency,
+    Cumulative,
+}
 
 impl SeriesType {
     fn series_specifier(&self, size: f32) -> String {
@@ -54,23 +149,74 @@ impl SeriesType {
             SeriesType::YERRORBAR => format!("yerrorbars ps {}", size),
         }
     }
-    fn linetype_specifier(&self, linetype: u32) -> String {
+    fn linetype_specifier(&self, linetype: &LineType) -> String {
         match *self {
-            SeriesType::Line => format!("dt {}", linetype),
-            SeriesType::Point | SeriesType::YERRORBAR => format!("pt {}", linetype),
+            SeriesType::Line => format!("dt {}", linetype.dash.specifier()),
+            SeriesType::Point | SeriesType::YERRORBAR => format!("pt {}", linetype.point),
+        }
+    }
+}
+impl Smooth {
+    fn new(arg: &str) -> Option<Smooth> {
+        match arg {
+            "csplines" => Some(Smooth::Csplines),
+            "acsplines" => Some(Smooth::Acsplines),
+            "bezier" => Some(Smooth::Bezier),
+            "sbezier" => Some(Smooth::Sbezier),
+            "kdensity" => Some(Smooth::Kdensity),
+            "unique" => Some(Smooth::Unique),
+            "frequency" => Some(Smooth::Frequency),
+            "cumulative" => Some(Smooth::Cumulative),
+            _ => None,
         }
     }
+    fn specifier(&self) -> String {
+        format!("smooth {}",
+                match *self {
+                    Smooth::Csplines => "csplines",
+                    Smooth::Acsplines => "acsplines",
+                    Smooth::Bezier => "bezier",
+                    Smooth::Sbezier => "sbezier",
+                    Smooth::Kdensity => "kdensity",
+                    Smooth::Unique => "unique",
+                    Smooth::Frequency => "frequency",
+                    Smooth::Cumulative => "cumulative",
+                })
+    }
 }
 impl PlotScript {
     fn new() -> PlotScript {
+        let paper = Theme::new("paper").unwrap();
         PlotScript {
             terminal: "pdf".to_string(),
-            font: "Times New Roman, 24".to_string(),
+            font: format!("{}, {}", paper.font_family, paper.font_size),
             delimiter: r"\t".to_string(),
             legend_position: "above".to_string(),
+            key_options: Vec::new(),
+            size: None,
+            scale: None,
+            size_ratio: None,
+            grid: false,
+            border: None,
+            key_box: true,
+            background: None,
+            foreground: None,
             plot: Vec::new(),
             x_label: "".to_string(),
             y_label: "".to_string(),
+            x_time: None,
+            x_format: None,
+            y_format: None,
+            title: None,
+            x_tics: AxisTics::default(),
+            y_tics: AxisTics::default(),
+            grid_tics: Vec::new(),
+            grid_style: None,
+            annotations: Vec::new(),
+            pre: Vec::new(),
+            post: Vec::new(),
+            comment: None,
+            missing: None,
         }
     }
 
@@ -90,6 +236,44 @@ impl PlotScript {
         self.legend_position = pos.join(" ");
         self
     }
+    /// other option of `set key`. (ex. `reverse Left`)
+    fn key_option(&mut self, o: String) -> &mut PlotScript {
+        self.key_options.push(o);
+        self
+    }
+    /// canvas size in terminal's units. (ex. 3.5in,2.5in)
+    fn size(&mut self, s: String) -> &mut PlotScript {
+        self.size = Some(s);
+        self
+    }
+    /// scale of fonts and lines, as raster terminals draw them in pixels.
+    fn scale(&mut self, s: f64) -> &mut PlotScript {
+        self.scale = Some(s);
+        self
+    }
+    /// `ratio R` or `square` of gnuplot's `set size`.
+    fn size_ratio(&mut self, r: String) -> &mut PlotScript {
+        self.size_ratio = Some(r);
+        self
+    }
+    fn grid(&mut self, g: bool) -> &mut PlotScript {
+        self.grid = g;
+        self
+    }
+    fn border(&mut self, b: u32) -> &mut PlotScript {
+        self.border = Some(b);
+        self
+    }
+    fn key_box(&mut self, k: bool) -> &mut PlotScript {
+        self.key_box = k;
+        self
+    }
+    /// colors of background, and of border, tics, labels and key text.
+    fn ink(&mut self, background: Color, foreground: Color) -> &mut PlotScript {
+        self.background = Some(background);
+        self.foreground = Some(foreground);
+        self
+    }
     fn x_label(&mut self, label: String) -> &mut PlotScript {
         self.x_label = label;
         self
@@ -98,22 +282,185 @@ impl PlotScript {
         self.y_label = label;
         self
     }
+    fn comment(&mut self, c: String) -> &mut PlotScript {
+        self.comment = Some(c);
+        self
+    }
+    fn missing(&mut self, m: String) -> &mut PlotScript {
+        self.missing = Some(m);
+        self
+    }
+    fn x_time(&mut self, t: TimeFormat) -> &mut PlotScript {
+        self.x_time = Some(t);
+        self
+    }
+    fn x_format(&mut self, f: String) -> &mut PlotScript {
+        self.x_format = Some(f);
+        self
+    }
+    fn y_format(&mut self, f: String) -> &mut PlotScript {
+        self.y_format = Some(f);
+        self
+    }
+    fn title(&mut self, t: String) -> &mut PlotScript {
+        self.title = Some(t);
+        self
+    }
+    fn x_tics(&mut self, t: AxisTics) -> &mut PlotScript {
+        self.x_tics = t;
+        self
+    }
+    fn y_tics(&mut self, t: AxisTics) -> &mut PlotScript {
+        self.y_tics = t;
+        self
+    }
+    /// tics with grid lines. (ex. `xtics`, `mytics`) major tics of both axes if empty.
+    fn grid_tics(&mut self, tics: Vec<String>) -> &mut PlotScript {
+        self.grid = true;
+        self.grid_tics = tics;
+        self
+    }
+    /// line properties of grid. (ex. `lc "gray" dt 3`)
+    fn grid_style(&mut self, s: String) -> &mut PlotScript {
+        self.grid_style = Some(s);
+        self
+    }
+    /// `set label`, `set arrow` or `set object` command drawn with series.
+    fn annotation(&mut self, command: String) -> &mut PlotScript {
+        self.annotations.push(command);
+        self
+    }
+    /// raw gnuplot commands after all settings, before plotting.
+    fn pre(&mut self, commands: String) -> &mut PlotScript {
+        self.pre.push(commands);
+        self
+    }
+    /// raw gnuplot commands after plotting, before writing the output file.
+    fn post(&mut self, commands: String) -> &mut PlotScript {
+        self.post.push(commands);
+        self
+    }
     fn plot(&mut self, series: Series) -> &mut PlotScript {
         self.plot.push(series);
         self
     }
     fn finalize(&self, output: String) -> String {
         let is_no_title = self.plot.iter().peekable().all(|ref p| p.title.is_none());
-        let legend_config = if is_no_title {String::from("")} else {format!("set key {}\nset key box lt 1 lc \"black\"\n",self.legend_position)};
-        let config = format!("set terminal {} enhanced font \"{}\"\nset datafile separator \
-                              \"{}\"\n{}set xlabel \
-                              \"{}\"\nset ylabel \"{}\"\nset output {}",
+        let foreground = self.foreground.clone().map(|c| c.specifier());
+        let text_color = foreground.clone()
+            .map(|c| format!(" textcolor {}", c))
+            .unwrap_or_default();
+        let legend_config = if is_no_title {
+            String::from("")
+        } else if self.legend_position == "off" {
+            String::from("set key off\n")
+        } else {
+            let key_box = if self.key_box {
+                format!("box lt 1 lc {}",
+                        foreground.clone().unwrap_or("\"black\"".to_string()))
+            } else {
+                "nobox".to_string()
+            };
+            let key_options = if self.key_options.is_empty() {
+                String::from("")
+            } else {
+                format!("set key {}\n", self.key_options.join(" "))
+            };
+            format!("set key {}{}\nset key {}\n{}",
+                    self.legend_position,
+                    text_color,
+                    key_box,
+                    key_options)
+        };
+        let terminal_options = format!("{}{}{}",
+                                       self.size
+                                           .clone()
+                                           .map(|s| format!(" size {}", s))
+                                           .unwrap_or_default(),
+                                       self.scale
+                                           .map(|s| format!(" fontscale {} linewidth {}", s, s))
+                                           .unwrap_or_default(),
+                                       self.background
+                                           .clone()
+                                           .map(|c| format!(" background {}", c.specifier()))
+                                           .unwrap_or_default());
+        let mut style_config = self.title
+            .clone()
+            .map(|t| format!("set title \"{}\"{}\n", t, text_color))
+            .unwrap_or_default();
+        if let Some(ref r) = self.size_ratio {
+            style_config += format!("set size {}\n", r).as_str();
+        }
+        if let Some(border) = self.border {
+            style_config += format!("set border {}\n", border).as_str();
+            if border != 15 {
+                style_config += "set tics nomirror\n";
+            }
+        }
+        if let Some(ref c) = foreground {
+            style_config += format!("set border lc {}\nset tics textcolor {}\n", c, c).as_str();
+        }
+        if self.grid {
+            let grid_tics = self.grid_tics.iter().map(|t| format!(" {}", t)).collect::<String>();
+            let grid_style = match (&self.grid_style, foreground) {
+                (Some(style), _) => format!(" {}", style),
+                (None, Some(c)) => format!(" lc {} dt 3", c),
+                (None, None) => String::new(),
+            };
+            style_config += format!("set grid{}{}\n", grid_tics, grid_style).as_str();
+        }
+        style_config += self.x_tics.config("x").as_str();
+        style_config += self.y_tics.config("y").as_str();
+        let separator = if self.delimiter == "whitespace" {
+            self.delimiter.clone()
+        } else {
+            format!("\"{}\"", self.delimiter)
+        };
+        let datafile_config = format!("{}{}",
+                                      self.comment
+                                          .clone()
+                                          .map(|c| {
+                                              format!("set datafile commentschars \"{}\"\n", c)
+                                          })
+                                          .unwrap_or_default(),
+                                      self.missing
+                                          .clone()
+                                          .map(|m| format!("set datafile missing \"{}\"\n", m))
+                                          .unwrap_or_default());
+        let x_time_config = self.x_time
+            .clone()
+            .map(|t| format!("set xdata time\nset timefmt \"{}\"\n", t.timefmt()))
+            .unwrap_or_default();
+        let x_format_config = format!("{}{}",
+                                      self.x_format
+                                          .clone()
+                                          .map(|f| format!("set format x \"{}\"\n", f))
+                                          .unwrap_or_default(),
+                                      self.y_format
+                                          .clone()
+                                          .map(|f| format!("set format y \"{}\"\n", f))
+                                          .unwrap_or_default());
+        let range_config = format!("{}{}",
+                                   self.x_tics.range_config("x"),
+                                   self.y_tics.range_config("y"));
+        let config = format!("set terminal {} enhanced font \"{}\"{}\nset datafile separator \
+                              {}\n{}{}{}set xlabel \
+                              \"{}\"{}\nset ylabel \"{}\"{}\n{}{}{}{}set output {}",
                              self.terminal,
                              self.font,
-                             self.delimiter,
+                             terminal_options,
+                             separator,
+                             datafile_config,
                              legend_config,
+                             style_config,
                              self.x_label,
+                             text_color,
                              self.y_label,
+                             text_color,
+                             x_time_config,
+                             x_format_config,
+                             self.annotations.join(""),
+                             self.pre.iter().map(|c| format!("{}\n", c)).collect::<String>(),
                              if cfg!(target_os = "windows") {
                                  "\"nul\""
                              } else {
@@ -124,12 +471,14 @@ impl PlotScript {
                 self.plot
                     .split_first()
                     .map(|(first, cons)| {
-                format!("\n\nplot {}\n{}set output \"{}\"\nreplot",
+                format!("\n\nplot {}\n{}{}{}set output \"{}\"\nreplot",
                         first.to_script(),
                         cons.iter()
                             .map(|plt| format!("replot {}\n", plt.to_script()))
                             .collect::<Vec<_>>()
                             .join(""),
+                        range_config,
+                        self.post.iter().map(|c| format!("{}\n", c)).collect::<String>(),
                         path_split_escaper(output))
             })
                     .unwrap_or("".to_string()))
@@ -138,56 +487,307 @@ impl PlotScript {
     }
 }
 impl Series {
-    fn new(file: String,
-           name: String,
-           ax: (u32, u32),
-           ye: Option<u32>,
-           typ: SeriesType,
-           size: f32,
-           cl: Color,
-           lt: u32)
-           -> Self {
+    fn new<A: Into<Expr>, L: Into<LineType>>(file: String,
+                                             name: String,
+                                             ax: (A, A),
+                                             ye: Option<Expr>,
+                                             typ: SeriesType,
+                                             size: f32,
+                                             cl: Color,
+                                             lt: L)
+                                             -> Self {
         Series {
             data_file: path_split_escaper(file),
             title: if name.len() == 0 { None } else { Some(name) },
-            axes: ax,
+            axes: (ax.0.into(), ax.1.into()),
             y_errorbar: ye,
+            y_range: None,
             s_type: typ,
             l_size: size,
             color: cl,
-            l_type: lt,
+            l_type: lt.into(),
+            smooth: None,
+            x_time_scale: None,
+            skip: 0,
+        }
+    }
+    fn smooth(&mut self, s: Smooth) -> &mut Series {
+        self.smooth = Some(s);
+        self
+    }
+    /// x column is epoch time in 1/`scale` seconds. it is given as expression to bypass `timefmt`.
+    fn x_time_scale(&mut self, scale: u32) -> &mut Series {
+        self.x_time_scale = Some(scale);
+        self
+    }
+    /// range of y drawn as error bars or a band. (low and high, not errors)
+    fn y_range(&mut self, low: Expr, high: Expr) -> &mut Series {
+        self.y_range = Some((low, high));
+        self
+    }
+    /// skip header rows at the beginning of data file.
+    fn skip(&mut self, rows: usize) -> &mut Series {
+        self.skip = rows;
+        self
+    }
+    /// compute trend from data file, and replace data file of this series by the derived one.
+    fn trend(&mut self,
+             trend: &Trend,
+             format: &DataFormat,
+             directory: &path::Path)
+             -> io::Result<Temp> {
+        self.derive(format, directory, |mut table| {
+            table[1] = trend.apply(&table[0], &table[1]);
+            table
+        })
+    }
+    /// decimate data file of `rows` data rows to at most `max_points` points, streaming rows.
+    /// `None` if it is already small enough.
+    fn downsample(&mut self,
+                  method: &Downsample,
+                  max_points: usize,
+                  rows: usize,
+                  format: &DataFormat,
+                  directory: &path::Path)
+                  -> io::Result<Option<Temp>> {
+        if rows <= max_points {
+            return Ok(None);
+        }
+        let columns = self.derived_columns();
+        let mut reducer = Reducer::new(method, rows, max_points);
+        let reader = io::BufReader::new(File::open(self.data_file.as_str())?);
+        data::for_each_row(reader, format, &columns, |i, row| reducer.push(i, row))?;
+        let kept = reducer.finish();
+        let table = (0..columns.len())
+            .map(|c| kept.iter().map(|row| row[c]).collect())
+            .collect::<Vec<_>>();
+        self.use_derived(&table, format, directory).map(Some)
+    }
+    /// replace (x, y) by statistic of y values at each x, with (low, high) range if `spread`.
+    fn aggregate(&mut self,
+                 statistic: &Aggregate,
+                 spread: Option<&Spread>,
+                 format: &DataFormat,
+                 directory: &path::Path)
+                 -> io::Result<Temp> {
+        let columns = [self.axes.0.clone(), self.axes.1.clone()];
+        let table = data::read_columns(self.data_file.as_str(), format, &columns)?;
+        let aggregated = aggregate::aggregate(&table[0], &table[1], statistic, spread);
+        let derived = self.replace_data(&aggregated, format, directory)?;
+        self.y_errorbar = None;
+        self.y_range = spread.map(|_| (Expr::Column(3), Expr::Column(4)));
+        Ok(derived)
+    }
+    /// read derived columns, and replace data file by the table `f` returns.
+    fn derive<F>(&mut self, format: &DataFormat, directory: &path::Path, f: F) -> io::Result<Temp>
+        where F: FnOnce(Vec<Vec<f64>>) -> Vec<Vec<f64>>
+    {
+        let table = data::read_columns(self.data_file.as_str(), format, &self.derived_columns())?;
+        self.use_derived(&f(table), format, directory)
+    }
+    /// (x, y[, y_error or low, high]) columns, which derived data files keep.
+    fn derived_columns(&self) -> Vec<Expr> {
+        let (x, y) = self.axes.clone();
+        let mut columns = vec![x, y];
+        columns.extend(self.y_error_axis());
+        if let Some((ref low, ref high)) = self.y_range {
+            columns.extend(vec![low.clone(), high.clone()]);
+        }
+        columns
+    }
+    /// replace data file by `table` of derived columns.
+    fn use_derived(&mut self,
+                   table: &[Vec<f64>],
+                   format: &DataFormat,
+                   directory: &path::Path)
+                   -> io::Result<Temp> {
+        let y_error = self.y_error_axis();
+        let derived = self.replace_data(table, format, directory)?;
+        self.y_errorbar = y_error.map(|_| Expr::Column(3));
+        if self.y_range.is_some() {
+            self.y_range = Some((Expr::Column(3), Expr::Column(4)));
+        }
+        Ok(derived)
+    }
+    /// write `table` of (x, y, ..) into a file which this series refers instead of input file.
+    /// time in x column is converted to seconds since epoch.
+    fn replace_data(&mut self,
+                    table: &[Vec<f64>],
+                    format: &DataFormat,
+                    directory: &path::Path)
+                    -> io::Result<Temp> {
+        let derived = data::write_columns(directory, &format.delimiter, table)?;
+        self.data_file = path_split_escaper(derived.as_ref().to_str().unwrap().to_string());
+        self.skip = 0;
+        self.axes = (Expr::Column(1), Expr::Column(2));
+        if let Some(TimeFormat::Format(_)) = format.x_time {
+            self.x_time_scale(1);
+        }
+        Ok(derived)
+    }
+    /// error column of yerrorbars. next to y column if it is not given.
+    fn y_error_axis(&self) -> Option<Expr> {
+        if self.s_type != SeriesType::YERRORBAR || self.y_range.is_some() {
+            return None;
+        }
+        self.y_errorbar
+            .clone()
+            .or_else(|| self.axes.1.column().map(|y| Expr::Column(y + 1)))
+    }
+    fn x_script(&self) -> String {
+        let x = &self.axes.0;
+        match self.x_time_scale {
+            Some(1) => format!("({})", x.to_expression()),
+            Some(scale) => format!("({}/{}.0)", x.operand(), scale),
+            None => x.to_script(),
+        }
+    }
+    fn skip_script(&self) -> String {
+        if self.skip > 0 {
+            format!("skip {} ", self.skip)
+        } else {
+            "".to_string()
+        }
+    }
+    /// range of y drawn as error bars for yerrorbars, as a band behind lines and points.
+    fn band_script(&self) -> String {
+        match self.y_range {
+            Some((ref low, ref high)) if self.s_type != SeriesType::YERRORBAR => {
+                format!("\"{}\" {}using {}:{}:{} notitle with filledcurves fc {} fs transparent \
+                         solid 0.25 noborder, ",
+                        self.data_file,
+                        self.skip_script(),
+                        self.x_script(),
+                        low.to_script(),
+                        high.to_script(),
+                        self.color.clone().specifier())
+            }
+            _ => "".to_string(),
         }
     }
     fn to_script(&self) -> String {
-        let (x, y) = self.axes;
-        format!("\"{}\" using {} {} with {} lc {} {}",
+        let y = &self.axes.1;
+        let y_range = match self.y_range {
+            Some((ref low, ref high)) if self.s_type == SeriesType::YERRORBAR => {
+                format!(":{}:{}", low.to_script(), high.to_script())
+            }
+            _ => "".to_string(),
+        };
+        format!("{}\"{}\" {}using {} {}{} with {} lc {} {}",
+                self.band_script(),
                 self.data_file,
-                format!("{}:{}{}",
-                        x,
-                        y,
-                        if self.s_type == SeriesType::YERRORBAR {
-                            format!(":{}", self.y_errorbar.unwrap_or(y + 1))
-                        } else {
-                            "".to_string()
-                        }),
+                self.skip_script(),
+                format!("{}:{}{}{}",
+                        self.x_script(),
+                        y.to_script(),
+                        self.y_error_axis()
+                            .map(|e| format!(":{}", e.to_script()))
+                            .unwrap_or_default(),
+                        y_range),
+                self.smooth.clone().map(|s| format!("{} ", s.specifier())).unwrap_or_default(),
                 self.title
                     .clone()
                     .map(|pat| format!("title \"{}\"", pat))
                     .unwrap_or(format!("notitle")),
                 self.s_type.series_specifier(self.l_size),
                 self.color.clone().specifier(),
-                self.s_type.linetype_specifier(self.l_type))
+                self.s_type.linetype_specifier(&self.l_type))
+    }
+}
+impl From<u32> for LineType {
+    fn from(n: u32) -> LineType {
+        LineType {
+            dash: Dash::Number(n),
+            point: n,
+        }
+    }
+}
+impl LineType {
+    /// number, dash pattern `(10,5,2,5)` for lines, or point type name (ex. `circle-open`).
+    /// dash pattern is up to 4 pairs of dash and gap, as gnuplot takes.
+    fn new(arg: &str) -> Option<LineType> {
+        if let Ok(n) = arg.parse::<u32>() {
+            return Some(LineType::from(n));
+        }
+        if let Some(point) = POINT_TYPES.iter().position(|&p| p == arg) {
+            return Some(LineType {
+                dash: Dash::Number(1),
+                point: point as u32 + 1,
+            });
+        }
+        let lengths = arg.strip_prefix('(')?
+            .strip_suffix(')')?
+            .split(',')
+            .map(|l| l.trim().parse::<u32>().ok().filter(|&l| l > 0))
+            .collect::<Option<Vec<_>>>()?;
+        if lengths.len() % 2 == 0 && lengths.len() <= 8 {
+            Some(LineType {
+                dash: Dash::Pattern(lengths),
+                point: 1,
+            })
+        } else {
+            None
+        }
+    }
+}
+impl Dash {
+    fn specifier(&self) -> String {
+        match *self {
+            Dash::Number(n) => n.to_string(),
+            Dash::Pattern(ref lengths) => {
+                format!("({})",
+                        lengths.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(","))
+            }
+        }
     }
 }
 impl Color {
+    /// color code (`#RGB`, `#RRGGBB`, `RRGGBB`, `#AARRGGBB`, `rgb()`, `rgba()` or `hsv()`),
+    /// otherwise a color name.
     fn new(arg: String) -> Color {
-        let rgb_regex = Regex::new(r"([a-f]|[A-F]|[0-9]){6}").unwrap();
-        if rgb_regex.is_match(arg.as_str()) {
-            Color::Code(arg)
+        let hex_regex = Regex::new(r"^(#[0-9a-fA-F]{3}|#?[0-9a-fA-F]{6}|#[0-9a-fA-F]{8})$")
+            .unwrap();
+        let code = arg.trim_start_matches('#');
+        if hex_regex.is_match(arg.as_str()) {
+            Color::Code(if code.len() == 3 {
+                code.chars().flat_map(|c| vec![c, c]).collect()
+            } else {
+                code.to_string()
+            })
         } else {
-            Color::Name(arg)
+            Color::function(arg.as_str()).map(Color::Code).unwrap_or(Color::Name(arg))
         }
     }
+    /// `rgb(R,G,B)` and `rgba(R,G,B,A)` with R, G, B in 0-255, and `hsv(H,S,V[,A])` with H in
+    /// degrees and S, V in 0-1. A is opacity in 0-1, turned into gnuplot's transparency.
+    fn function(arg: &str) -> Option<String> {
+        let (name, rest) = arg.trim().split_at(arg.trim().find('(')?);
+        let args = rest.strip_prefix('(')?
+            .strip_suffix(')')?
+            .split(',')
+            .map(|a| a.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+            .collect::<Option<Vec<_>>>()?;
+        let in_unit = |v: f64| if (0.0..=1.0).contains(&v) { Some(v) } else { None };
+        let (rgb, alpha) = match (name, args.len()) {
+            ("rgb", 3) | ("rgba", 4) => {
+                let channel = |v: f64| in_unit(v / 255.0);
+                ((channel(args[0])?, channel(args[1])?, channel(args[2])?),
+                 args.get(3).map_or(Some(1.0), |&a| in_unit(a))?)
+            }
+            ("hsv", 3) | ("hsv", 4) => {
+                (hsv_to_rgb(args[0].rem_euclid(360.0), in_unit(args[1])?, in_unit(args[2])?),
+                 args.get(3).map_or(Some(1.0), |&a| in_unit(a))?)
+            }
+            _ => return None,
+        };
+        let byte = |v: f64| (v * 255.0).round() as u8;
+        let code = format!("{:02x}{:02x}{:02x}", byte(rgb.0), byte(rgb.1), byte(rgb.2));
+        Some(if alpha < 1.0 {
+            format!("{:02x}{}", byte(1.0 - alpha), code)
+        } else {
+            code
+        })
+    }
     fn specifier(self) -> String {
         match self {
             Color::Name(expr) => format!("\"{}\"", expr),
@@ -195,133 +795,148 @@ impl Color {
         }
     }
 }
+fn hsv_to_rgb(h: f64, s: f64, v: f64) -> (f64, f64, f64) {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    (r + v - c, g + v - c, b + v - c)
+}
 fn axes_validator(arg: String) -> Result<(), String> {
-    let axes_regex = Regex::new(r"^[1-9]\d*:[1-9]\d*(:[1-9]\d*)?$").unwrap();
-    if arg.split(",").all(|s| axes_regex.is_match(s)) {
+    match expr::split_top_level(arg.as_str(), ',')
+        .iter()
+        .map(|s| expr::parse_axes(s))
+        .find(|a| a.is_err()) {
+        Some(Err(e)) => Err(format!("axes format is invalid .. {}", e)),
+        _ => Ok(()),
+    }
+}
+/// parse `FILE:x:y[:y_error]` or `@N:x:y[:y_error]` (N-th input file) of `--plot`.
+fn plot_spec(arg: &str) -> Option<(String, Vec<Expr>)> {
+    let parts = expr::split_top_level(arg, ':');
+    let axes = parts.iter()
+        .rev()
+        .take(3)
+        .map(|p| Expr::new(p))
+        .take_while(|a| a.is_ok())
+        .map(|a| a.unwrap())
+        .collect::<Vec<_>>();
+    // file name itself may look like a column name
+    let axes_count = if axes.len() == parts.len() { axes.len() - 1 } else { axes.len() };
+    if axes_count < 2 {
+        return None;
+    }
+    let file = parts[..parts.len() - axes_count].join(":");
+    Some((file, axes.into_iter().take(axes_count).rev().collect()))
+}
+fn plot_spec_validator(arg: String) -> Result<(), String> {
+    if plot_spec(arg.as_str()).is_some() {
         Ok(())
     } else {
-        Err(String::from("axes format is invalid .."))
+        Err(String::from("plot format is invalid. (ex. data.csv:1:2 or @1:1:2)"))
+    }
+}
+/// pairs of (data file, axes) for each series.
+/// `--plot` maps series to files explicitly. otherwise, `--axis` groups are mapped to input files
+/// in order, or one group is applied to all of input files.
+fn series_sources(inputs: &[String],
+                  axes: &[Vec<Vec<Expr>>],
+                  plots: &[(String, Vec<Expr>)])
+                  -> Result<Vec<(String, Vec<Expr>)>, String> {
+    if plots.is_empty() {
+        if axes.len() != inputs.len() && axes.len() != 1 {
+            return Err(format!("{} axes groups are given for {} input files. give one group, or \
+                                one group for each input file.",
+                               axes.len(),
+                               inputs.len()));
+        }
+        return Ok(inputs.iter()
+            .enumerate()
+            .flat_map(|(i, file)| {
+                axes[if axes.len() == 1 { 0 } else { i }]
+                    .iter()
+                    .map(move |ax| (file.clone(), ax.clone()))
+            })
+            .collect());
+    }
+    let mut used = vec![false; inputs.len()];
+    let sources = plots.iter()
+        .map(|(file, ax)| if let Some(index) = file.strip_prefix('@') {
+            match index.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= inputs.len() => {
+                    used[n - 1] = true;
+                    Ok((inputs[n - 1].clone(), ax.clone()))
+                }
+                _ => Err(format!("{} refers to input #{}, but {} input files are given.",
+                                 file,
+                                 index,
+                                 inputs.len())),
+            }
+        } else {
+            // a plot may also name an input by its path
+            for (j, input) in inputs.iter().enumerate() {
+                if input == file {
+                    used[j] = true;
+                }
+            }
+            Ok((file.clone(), ax.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match used.iter().position(|&u| !u) {
+        Some(i) => Err(format!("input #{} ({}) is not used by any plot.", i + 1, inputs[i])),
+        None => Ok(sources),
     }
 }
+fn key_column_validator(arg: String) -> Result<(), String> {
+    match Expr::new(arg.as_str()) {
+        Ok(Expr::Column(n)) if n > 0 => Ok(()),
+        Ok(Expr::Named(_)) => Ok(()),
+        _ => Err(String::from("key column must be a column number or a column name.")),
+    }
+}
+/// color names of gnuplot, as listed by `show colornames`.
+const COLOR_NAMES: [&str; 111] = ["white", "black", "dark-grey", "red", "web-green", "web-blue",
+                                  "dark-magenta", "dark-cyan", "dark-orange", "dark-yellow",
+                                  "royalblue", "goldenrod", "dark-spring-green", "purple",
+                                  "steelblue", "dark-red", "dark-chartreuse", "orchid",
+                                  "aquamarine", "brown", "yellow", "turquoise", "grey0", "grey10",
+                                  "grey20", "grey30", "grey40", "grey50", "grey60", "grey70",
+                                  "grey", "grey80", "grey90", "grey100", "light-red",
+                                  "light-green", "light-blue", "light-magenta", "light-cyan",
+                                  "light-goldenrod", "light-pink", "light-turquoise", "gold",
+                                  "green", "dark-green", "spring-green", "forest-green",
+                                  "sea-green", "blue", "dark-blue", "midnight-blue", "navy",
+                                  "medium-blue", "skyblue", "cyan", "magenta", "dark-turquoise",
+                                  "dark-pink", "coral", "light-coral", "orange-red", "salmon",
+                                  "dark-salmon", "khaki", "dark-khaki", "dark-goldenrod", "beige",
+                                  "olive", "orange", "violet", "dark-violet", "plum", "dark-plum",
+                                  "dark-olivegreen", "orangered4", "brown4", "sienna4", "orchid4",
+                                  "mediumpurple3", "slateblue1", "yellow4", "sienna1", "tan1",
+                                  "sandybrown", "light-salmon", "pink", "khaki1", "lemonchiffon",
+                                  "bisque", "honeydew", "slategrey", "seagreen", "antiquewhite",
+                                  "chartreuse", "greenyellow", "gray", "light-gray", "light-grey",
+                                  "dark-gray", "slategray", "gray0", "gray10", "gray20", "gray30",
+                                  "gray40", "gray50", "gray60", "gray70", "gray80", "gray90",
+                                  "gray100"];
 fn colors_validator(arg: String) -> Result<(), String> {
-    let arg_list: Vec<_> = arg.split(",").collect();
-    let rgb_regex = Regex::new(r"^([a-f]|[A-F]|[0-9]){6}$").unwrap();
-    let color_list = ["white",
-                      "black",
-                      "dark-grey",
-                      "red",
-                      "web-green",
-                      "web-blue",
-                      "dark-magenta",
-                      "dark-cyan",
-                      "dark-orange",
-                      "radk-yellow",
-                      "royalblue",
-                      "goldenrod",
-                      "dark-spring-green",
-                      "purple",
-                      "steelblue",
-                      "dark-red",
-                      "dark-chartreuse",
-                      "orchid",
-                      "aquamarine",
-                      "brown",
-                      "yellow",
-                      "turquoise",
-                      "grey0",
-                      "grey10",
-                      "grey20",
-                      "grey30",
-                      "grey40",
-                      "grey50",
-                      "grey60",
-                      "grey70",
-                      "grey",
-                      "grey80",
-                      "grey90",
-                      "grey100",
-                      "light-red",
-                      "light-green",
-                      "light-blue",
-                      "light-magenta",
-                      "light-cyan",
-                      "light-goldenrod",
-                      "light-pink",
-                      "light-turquoise",
-                      "gold",
-                      "green",
-                      "dark-green",
-                      "spring-green",
-                      "forest-green",
-                      "sea-green",
-                      "blue",
-                      "dark-blue",
-                      "midnight-blue",
-                      "navy",
-                      "midium-blue",
-                      "skyblue",
-                      "cyan",
-                      "magenta",
-                      "dark-turquoise",
-                      "dark-pink",
-                      "coral",
-                      "light-coral",
-                      "orange-red",
-                      "salmon",
-                      "dark-salmon",
-                      "khaki",
-                      "dark-khaki",
-                      "dark-goldenrod",
-                      "beige",
-                      "olive",
-                      "orange",
-                      "violet",
-                      "dark-violet",
-                      "plum",
-                      "dark-plum",
-                      "dark-olivegreen",
-                      "orangered4",
-                      "brown4",
-                      "sienna4",
-                      "orchid4",
-                      "mediumpurple3",
-                      "slateblue1",
-                      "yellow4",
-                      "sienna1",
-                      "tan1",
-                      "sandybrown",
-                      "light-salmon",
-                      "pink",
-                      "khaki1",
-                      "lemonchiffon",
-                      "bisque",
-                      "honeydew",
-                      "slategrey",
-                      "seagreen",
-                      "antiquewhite",
-                      "chartreuse",
-                      "greenyellow",
-                      "gray",
-                      "light-gray",
-                      "light-grey",
-                      "dark-gray",
-                      "slategray",
-                      "gray0",
-                      "gray10",
-                      "gray20",
-                      "gray30",
-                      "gray40",
-                      "gray50",
-                      "gray60",
-                      "gray70",
-                      "gray80",
-                      "gray90",
-                      "gray100"];
-
-    if arg_list.iter().all(|it| color_list.contains(it) | rgb_regex.is_match(it)) {
-        Ok(())
-    } else {
-        Err(String::from("invalid color {name,code}"))
+    match expr::split_top_level(arg.as_str(), ',')
+        .into_iter()
+        .find(|c| match Color::new(c.clone()) {
+            Color::Name(name) => !COLOR_NAMES.contains(&name.as_str()),
+            Color::Code(_) => false,
+        }) {
+        Some(c) => {
+            Err(format!("invalid color \"{}\". (gnuplot color name, #RGB, #RRGGBB, #AARRGGBB, \
+                         rgb(R,G,B), rgba(R,G,B,A) or hsv(H,S,V[,A]))",
+                        c))
+        }
+        None => Ok(()),
     }
 }
 fn widths_validator(arg: String) -> Result<(), String> {
@@ -332,24 +947,280 @@ fn widths_validator(arg: String) -> Result<(), String> {
         Err(String::from("width value is not number."))
     }
 }
+fn trends_validator(arg: String) -> Result<(), String> {
+    if arg.split(",").all(|s| s == "none" || Trend::new(s).is_some()) {
+        Ok(())
+    } else {
+        Err(String::from("trend is invalid. (none, ma:N, ema:ALPHA or loess:SPAN)"))
+    }
+}
+fn size_validator(arg: String) -> Result<(), String> {
+    if Size::new(arg.as_str()).is_some() {
+        Ok(())
+    } else {
+        Err(String::from("size must be WIDTH,HEIGHT with unit in, cm or px. (ex. 3.5in,2.5in)"))
+    }
+}
+fn positive_number_validator(arg: String) -> Result<(), String> {
+    match arg.parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(()),
+        _ => Err(String::from("value must be a positive number.")),
+    }
+}
+fn number_validator(arg: String) -> Result<(), String> {
+    match arg.parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(()),
+        _ => Err(String::from("value must be a number.")),
+    }
+}
+fn tics_validator(arg: String) -> Result<(), String> {
+    if Tics::new(arg.as_str()).is_some() {
+        Ok(())
+    } else {
+        Err(String::from("tics must be a step (0.5), a list (1,10,100) or a count (count:5)."))
+    }
+}
+fn positive_integer_validator(arg: String) -> Result<(), String> {
+    match arg.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(()),
+        _ => Err(String::from("value must be a positive integer.")),
+    }
+}
+/// check that series (1-origin) given to `option` are plotted.
+fn series_check(option: &str, count: usize, series: &[usize]) -> Result<(), String> {
+    match series.iter().find(|&&n| n > count) {
+        Some(n) => {
+            Err(format!("--{} refers to series #{}, but {} series are plotted.", option, n, count))
+        }
+        None => Ok(()),
+    }
+}
+/// indices of series in the order of key entries. series in `order` (1-origin) come first, and
+/// others follow in their own order.
+fn key_order(count: usize, order: &[usize]) -> Result<Vec<usize>, String> {
+    series_check("key-order", count, order)?;
+    let mut indices = Vec::new();
+    for i in order.iter().map(|n| n - 1).chain(0..count) {
+        if !indices.contains(&i) {
+            indices.push(i);
+        }
+    }
+    Ok(indices)
+}
+fn max_points_validator(arg: String) -> Result<(), String> {
+    match arg.parse::<usize>() {
+        Ok(n) if n >= 3 => Ok(()),
+        _ => Err(String::from("max points must be an integer (>= 3).")),
+    }
+}
+fn delimiter_validator(arg: String) -> Result<(), String> {
+    if arg == "auto" || Delimiter::new(arg.as_str()).is_some() {
+        Ok(())
+    } else {
+        Err(String::from("delimiter must be a character, auto, comma, tab, semicolon or \
+                          whitespace."))
+    }
+}
+fn skip_rows_validator(arg: String) -> Result<(), String> {
+    if arg.parse::<usize>().is_ok() {
+        Ok(())
+    } else {
+        Err(String::from("number of rows is invalid (not positive number)."))
+    }
+}
+fn binary_format_validator(arg: String) -> Result<(), String> {
+    if BinaryFormat::new(arg.as_str()).is_some() {
+        Ok(())
+    } else {
+        Err(String::from("binary format must be f32, f64, f32:N or f64:N."))
+    }
+}
+fn palette_validator(arg: String) -> Result<(), String> {
+    if Palette::new(arg.as_str()).is_some() {
+        Ok(())
+    } else {
+        Err(String::from("palette must be tableau10, okabe-ito, viridis:N, set1 or grayscale."))
+    }
+}
+/// point types of gnuplot's pdf terminal in the order of `pt` numbers.
+const POINT_TYPES: [&str; 15] = ["plus", "cross", "star", "square-open", "square", "circle-open",
+                                 "circle", "triangle-open", "triangle", "triangle-down-open",
+                                 "triangle-down", "diamond-open", "diamond", "pentagon-open",
+                                 "pentagon"];
+/// (dash, point) of series in `--mono`, distinguishable without colors.
+const MONO_TYPES: [(&str, &str); 8] = [("1", "circle-open"),
+                                       ("(8,4)", "square-open"),
+                                       ("(2,4)", "triangle-open"),
+                                       ("(12,4,2,4)", "diamond-open"),
+                                       ("(12,4,2,4,2,4)", "triangle-down-open"),
+                                       ("(4,8)", "pentagon-open"),
+                                       ("(16,8)", "plus"),
+                                       ("(2,2)", "cross")];
 fn linetypes_validator(arg: String) -> Result<(), String> {
-    let linetype_regex = Regex::new(r"^\d+$").unwrap();
-    if arg.split(",").all(|s| linetype_regex.is_match(s)) {
+    if expr::split_top_level(arg.as_str(), ',').iter().all(|s| LineType::new(s).is_some()) {
         Ok(())
     } else {
-        Err(String::from("linetype value is invalid (not positive number)."))
+        Err(String::from("linetype value is invalid (not positive number, dash pattern of up \
+                          to 4 pairs as (10,5,2,5) or point type name)."))
     }
 }
+/// gnuplot command of an annotation, checking its color and dash pattern.
+fn annotation_command(annotation: &Annotation) -> Result<String, String> {
+    let color = match annotation.color {
+        Some(ref c) => {
+            colors_validator(c.clone())?;
+            Some(Color::new(c.clone()).specifier())
+        }
+        None => None,
+    };
+    let dash = match annotation.dash {
+        Some(ref d) => {
+            match LineType::new(d).filter(|_| !POINT_TYPES.contains(&d.as_str())) {
+                Some(l) => Some(l.dash.specifier()),
+                None => {
+                    return Err(format!("invalid dash pattern \"{}\". (number or pattern as \
+                                        (10,5))",
+                                       d))
+                }
+            }
+        }
+        None => None,
+    };
+    Ok(annotation.command(color, dash))
+}
+fn exit_with_error(description: &str, kind: clap::ErrorKind) -> ! {
+    clap::Error::with_description(description, kind).exit()
+}
 fn path_split_escaper(s: String) -> String {
     let separator_regex =
         Regex::new(regex::escape(path::MAIN_SEPARATOR.to_string().as_str()).as_str()).unwrap();
     separator_regex.replace_all(s.as_str(), r"/").to_string()
 }
+/// `inspect` subcommand: print what is found in each input file, to choose axes by.
+fn inspect_files(args: &clap::ArgMatches) {
+    let mut data_format = DataFormat::new(Delimiter::Char(','));
+    data_format.x_time = args.value_of("xtime").map(TimeFormat::new);
+    data_format.skip_rows = args.value_of("skip_rows").unwrap().parse::<usize>().unwrap();
+    data_format.comment = args.value_of("comment").map(|c| c.to_string());
+    data_format.missing = args.value_of("missing").map(|m| m.to_string());
+    let delimiter = args.value_of("delimiter").and_then(Delimiter::new);
+    let conversion = input::Conversion {
+        binary: args.value_of("binary_format").and_then(BinaryFormat::new),
+        query: args.value_of("query").map(|q| q.to_string()),
+        delimiter: delimiter.clone().unwrap_or(Delimiter::Char(',')),
+    };
+    let mut summaries = Vec::new();
+    for file in args.values_of("FILES").unwrap() {
+        let directory = path::Path::new(file).parent().unwrap();
+        let converted = input::convert(file, &conversion, directory).unwrap_or_else(|e| {
+            exit_with_error(format!("failed to convert {}: {}", file, e).as_str(),
+                            clap::ErrorKind::Io)
+        });
+        let data_file = converted.as_ref()
+            .map(|c| c.as_ref().to_str().unwrap().to_string())
+            .unwrap_or_else(|| file.to_string());
+        data_format.delimiter = match delimiter {
+            Some(ref delimiter) => delimiter.clone(),
+            None => {
+                data::detect_delimiter(data_file.as_str(), &data_format)
+                    .expect("failed to read input file. ")
+            }
+        };
+        let normalized = if args.is_present("decimal_comma") {
+            if data_format.delimiter == Delimiter::Char(',') {
+                exit_with_error(format!("{}: --decimal-comma cannot be used with comma delimiter.",
+                                        file)
+                                    .as_str(),
+                                clap::ErrorKind::ArgumentConflict);
+            }
+            Some(data::normalize(data_file.as_str(),
+                                 &data_format,
+                                 &data_format.delimiter,
+                                 true,
+                                 directory)
+                .expect("failed to read input file. "))
+        } else {
+            None
+        };
+        let data_file = normalized.as_ref()
+            .map(|n| n.as_ref().to_str().unwrap().to_string())
+            .unwrap_or(data_file);
+        let reader = io::BufReader::new(File::open(data_file)
+            .expect("failed to open input file. "));
+        let summary = inspect::summarize(reader, &data_format)
+            .expect("failed to read input file. ");
+        summaries.push((file, summary));
+    }
+    if args.is_present("json") {
+        let json = summaries.iter().map(|(file, s)| s.to_json(file)).collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&json).unwrap());
+    } else {
+        let texts = summaries.iter().map(|(file, s)| s.to_text(file)).collect::<Vec<_>>();
+        print!("{}", texts.join("\n"));
+    }
+}
+/// arguments on how input files are read, shared by plotting and `inspect`.
+fn data_format_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![Arg::with_name("delimiter")
+             .help("delimiter of input files. (auto, comma, tab, semicolon, whitespace or a \
+                    character)")
+             .long("delimiter")
+             .takes_value(true)
+             .default_value("auto")
+             .validator(delimiter_validator),
+         Arg::with_name("skip_rows")
+             .help("number of header rows skipped in each input file.")
+             .long("skip-rows")
+             .takes_value(true)
+             .default_value("0")
+             .validator(skip_rows_validator),
+         Arg::with_name("comment")
+             .help("characters which start comment lines in input files. [default: #!]")
+             .long("comment")
+             .takes_value(true),
+         Arg::with_name("missing")
+             .help("string which marks missing values in input files. (ex. NA)")
+             .long("missing")
+             .takes_value(true),
+         Arg::with_name("decimal_comma")
+             .help("input files use comma as decimal sign. (ex. 1,5)")
+             .long("decimal-comma")
+             .takes_value(false),
+         Arg::with_name("binary_format")
+             .help("input .bin and .raw files are raw little-endian binaries of this format. \
+                    (f32, f64, f32:N or f64:N for N columns)")
+             .long("binary-format")
+             .takes_value(true)
+             .validator(binary_format_validator),
+         Arg::with_name("query")
+             .help("SQL query run against SQLite input files. (.db, .sqlite or .sqlite3) without \
+                    --axis, columns of results are plotted against the first column.")
+             .long("query")
+             .takes_value(true),
+         Arg::with_name("xtime")
+             .help("time format of x values. (strptime format as \"%Y-%m-%dT%H:%M:%S\", epoch \
+                    or epoch-ms)")
+             .long("xtime")
+             .takes_value(true)]
+}
 fn main() {
     let app = app_from_crate!()
+        .setting(clap::AppSettings::SubcommandsNegateReqs)
+        .subcommand(clap::SubCommand::with_name("inspect")
+            .about("print delimiter, header, columns, types, row counts, missing counts and \
+                    min/max of input files.")
+            .arg(Arg::with_name("FILES")
+                .help("input file names")
+                .required(true)
+                .multiple(true))
+            .args(&data_format_args())
+            .arg(Arg::with_name("json")
+                .help("print summaries as JSON.")
+                .long("json")
+                .takes_value(false)))
         .arg(Arg::with_name("INPUTS")
             .help("input file names")
-            .required(true)
+            .required_unless_one(&["plots", "criterion"])
             .multiple(true)
             .short("i")
             .long("input")
@@ -376,6 +1247,156 @@ fn main() {
             .takes_value(true)
             .multiple(false)
             .default_value(""))
+        .args(&data_format_args())
+        .arg(Arg::with_name("criterion")
+            .help("directory of criterion.rs results (ex. target/criterion) plotted instead of \
+                   input files. x is the parameter of each benchmark.")
+            .long("criterion")
+            .takes_value(true)
+            .conflicts_with_all(&["INPUTS", "plots", "join"]))
+        .arg(Arg::with_name("estimate")
+            .help("estimate of criterion.rs results plotted with its confidence interval.")
+            .long("estimate")
+            .takes_value(true)
+            .requires("criterion")
+            .possible_values(&["mean", "median", "slope"])
+            .default_value("mean"))
+        .arg(Arg::with_name("strict")
+            .help("fail on non-numeric cells in columns of axes, not only on columns without \
+                   numbers.")
+            .long("strict")
+            .takes_value(false))
+        .arg(Arg::with_name("xformat")
+            .help("format of x tic labels. (ex. \"%H:%M\" for time, si, sci or percent, which \
+                   appends % to values already in percent)")
+            .long("xformat")
+            .takes_value(true))
+        .arg(Arg::with_name("yformat")
+            .help("format of y tic labels. (ex. \"%.2f\", si, sci or percent, which appends % to \
+                   values already in percent)")
+            .long("yformat")
+            .takes_value(true))
+        .arg(Arg::with_name("figure_title")
+            .help("title of the figure. (-t --title is for titles of series)")
+            .long("figure-title")
+            .takes_value(true))
+        .arg(Arg::with_name("grid")
+            .help("draw grid lines at tics. (x, y, mx or my for minor tics) [default: x,y]")
+            .long("grid")
+            .takes_value(true)
+            .multiple(true)
+            .min_values(0)
+            .require_delimiter(true)
+            .possible_values(&["x", "y", "mx", "my"]))
+        .arg(Arg::with_name("grid_style")
+            .help("line properties of grid lines. (ex. \"lc 'gray' dt 3\")")
+            .long("grid-style")
+            .takes_value(true))
+        .arg(Arg::with_name("xtics")
+            .help("major x tics by step (0.5), list (1,10,100) or count (count:5).")
+            .long("xtics")
+            .takes_value(true)
+            .use_delimiter(false)
+            .validator(tics_validator))
+        .arg(Arg::with_name("ytics")
+            .help("major y tics by step (0.5), list (1,10,100) or count (count:5).")
+            .long("ytics")
+            .takes_value(true)
+            .use_delimiter(false)
+            .validator(tics_validator))
+        .arg(Arg::with_name("mxtics")
+            .help("minor x tics, with the number of intervals between major tics.")
+            .long("mxtics")
+            .takes_value(true)
+            .min_values(0)
+            .validator(positive_integer_validator))
+        .arg(Arg::with_name("mytics")
+            .help("minor y tics, with the number of intervals between major tics.")
+            .long("mytics")
+            .takes_value(true)
+            .min_values(0)
+            .validator(positive_integer_validator))
+        .arg(Arg::with_name("xtics_rotate")
+            .help("rotation of x tic labels in degrees.")
+            .long("xtics-rotate")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .validator(number_validator))
+        .arg(Arg::with_name("ytics_rotate")
+            .help("rotation of y tic labels in degrees.")
+            .long("ytics-rotate")
+            .takes_value(true)
+            .allow_hyphen_values(true)
+            .validator(number_validator))
+        .arg(Arg::with_name("label")
+            .help("text at a point, followed by color. (ex. --label \"p99\" at 3,99.9,red)")
+            .long("label")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(false)
+            .number_of_values(3)
+            .value_names(&["TEXT", "at", "X,Y"])
+            .allow_hyphen_values(true))
+        .arg(Arg::with_name("arrow")
+            .help("arrow between points, followed by color and dash. (ex. 1,2:3,4,red)")
+            .long("arrow")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(false)
+            .number_of_values(1)
+            .allow_hyphen_values(true))
+        .arg(Arg::with_name("hline")
+            .help("horizontal line at y, followed by color and dash. (ex. 99.9,red,2)")
+            .long("hline")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(false)
+            .number_of_values(1)
+            .allow_hyphen_values(true))
+        .arg(Arg::with_name("vline")
+            .help("vertical line at x, followed by color and dash. (ex. 2017-03-04,gray)")
+            .long("vline")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(false)
+            .number_of_values(1)
+            .allow_hyphen_values(true))
+        .arg(Arg::with_name("span")
+            .help("shaded region between x values, followed by color. (ex. 1:2,gray)")
+            .long("span")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(false)
+            .number_of_values(1)
+            .allow_hyphen_values(true))
+        .arg(Arg::with_name("annotations")
+            .help("file of annotations in rows as label,TEXT at X,Y or hline,99.9,red")
+            .long("annotations")
+            .takes_value(true))
+        .arg(Arg::with_name("include")
+            .help("gnuplot script file added as --pre of its commands. included files run before \
+                   --pre commands.")
+            .long("include")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .use_delimiter(false))
+        .arg(Arg::with_name("pre")
+            .help("gnuplot command run after settings, before plotting. (ex. \"set logscale y\")")
+            .long("pre")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .use_delimiter(false)
+            .allow_hyphen_values(true))
+        .arg(Arg::with_name("post")
+            .help("gnuplot command run after plotting, before writing output file.")
+            .long("post")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .use_delimiter(false)
+            .allow_hyphen_values(true))
         .arg(Arg::with_name("axes")
             .help("axes in input file. (ex. x_a:y_a,x_b:y_b, ...)")
             .short("a")
@@ -385,8 +1406,73 @@ fn main() {
             .require_delimiter(false)
             .default_value("1:2")
             .validator(axes_validator))
+        .arg(Arg::with_name("plots")
+            .help("input file and axes in each series. (ex. data.csv:1:2, @2:1:3 for 2nd input)")
+            .short("p")
+            .long("plot")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(plot_spec_validator))
+        .arg(Arg::with_name("group_by")
+            .help("column whose values split rows of each input file into series. (number or \
+                   name)")
+            .long("group-by")
+            .takes_value(true)
+            .validator(key_column_validator))
+        .arg(Arg::with_name("join")
+            .help("column whose values align rows of input files. (number or name) columns of \
+                   N-th input are referred as N@2, name@2 or $N@2 in axes.")
+            .long("join")
+            .takes_value(true)
+            .validator(key_column_validator))
+        .arg(Arg::with_name("key")
+            .help("position of key. (ex. outside,right,top) off hides the key, alone.")
+            .long("key")
+            .takes_value(true)
+            .multiple(true)
+            .require_delimiter(true)
+            .possible_values(&["inside", "outside", "above", "below", "left", "right", "center",
+                               "top", "bottom", "off"]))
+        .arg(Arg::with_name("key_columns")
+            .help("maximum number of columns of key entries.")
+            .long("key-columns")
+            .takes_value(true)
+            .validator(positive_integer_validator))
+        .arg(Arg::with_name("no_key_box")
+            .help("draw key without box.")
+            .long("no-key-box")
+            .takes_value(false))
+        .arg(Arg::with_name("key_font")
+            .help("font of key. (ex. \"Helvetica, 10\")")
+            .long("key-font")
+            .takes_value(true))
+        .arg(Arg::with_name("key_reverse")
+            .help("put samples of series left of titles in key.")
+            .long("key-reverse")
+            .takes_value(false))
+        .arg(Arg::with_name("key_spacing")
+            .help("vertical spacing of key entries, relative to font size.")
+            .long("key-spacing")
+            .takes_value(true)
+            .validator(positive_number_validator))
+        .arg(Arg::with_name("key_order")
+            .help("series (1-origin) listed first in key, in this order. others follow. series \
+                   are drawn in the order of key, so later ones are drawn on top.")
+            .long("key-order")
+            .takes_value(true)
+            .multiple(true)
+            .require_delimiter(true)
+            .validator(positive_integer_validator))
+        .arg(Arg::with_name("key_hide")
+            .help("series (1-origin) hidden from key.")
+            .long("key-hide")
+            .takes_value(true)
+            .multiple(true)
+            .require_delimiter(true)
+            .validator(positive_integer_validator))
         .arg(Arg::with_name("titles")
-            .help("title in each series.")
+            .help("title in each series. (title of the figure is --figure-title)")
             .short("t")
             .long("title")
             .takes_value(true)
@@ -394,14 +1480,30 @@ fn main() {
             .require_delimiter(true)
             .default_value(""))
         .arg(Arg::with_name("colors")
-            .help("plot color in each axes.")
+            .help("plot color in each axes. [default: color cycle of theme]")
             .short("c")
             .long("color")
             .takes_value(true)
             .multiple(true)
-            .require_delimiter(true)
-            .default_value("black")
+            .use_delimiter(false)
             .validator(colors_validator))
+        .arg(Arg::with_name("palette")
+            .help("color cycle used when colors are not given. (tableau10, okabe-ito, \
+                   viridis:N, set1 or grayscale) okabe-ito is colorblind-safe.")
+            .long("palette")
+            .takes_value(true)
+            .validator(palette_validator))
+        .arg(Arg::with_name("mono")
+            .help("black and gray series with distinct dash patterns and point types, for \
+                   grayscale printing.")
+            .long("mono")
+            .takes_value(false)
+            .conflicts_with_all(&["colors", "palette"]))
+        .arg(Arg::with_name("distinct_types")
+            .help("cycle dash types of lines and point types of points over series when line \
+                   types are not given.")
+            .long("distinct-types")
+            .takes_value(false))
         .arg(Arg::with_name("seriestypes")
             .help("series type in each series.")
             .short("s")
@@ -412,13 +1514,12 @@ fn main() {
             .possible_values(&["l", "p", "y"])
             .default_value("l"))
         .arg(Arg::with_name("widths")
-            .help("each line width")
+            .help("each line width [default: by theme]")
             .short("w")
             .long("width")
             .takes_value(true)
             .multiple(true)
             .require_delimiter(true)
-            .default_value("1")
             .validator(widths_validator))
         .arg(Arg::with_name("linetypes")
             .help("line type in each series.")
@@ -426,101 +1527,805 @@ fn main() {
             .long("linetype")
             .takes_value(true)
             .multiple(true)
-            .require_delimiter(true)
+            .use_delimiter(false)
             .default_value("1")
             .validator(linetypes_validator))
+        .arg(Arg::with_name("smooths")
+            .help("gnuplot smooth option in each series.")
+            .long("smooth")
+            .takes_value(true)
+            .multiple(true)
+            .require_delimiter(true)
+            .possible_values(&["none", "csplines", "acsplines", "bezier", "sbezier", "kdensity",
+                               "unique", "frequency", "cumulative"]))
+        .arg(Arg::with_name("trends")
+            .help("trend computed from data in each series. (none, ma:N, ema:ALPHA, loess:SPAN)")
+            .long("trend")
+            .takes_value(true)
+            .multiple(true)
+            .require_delimiter(true)
+            .validator(trends_validator))
+        .arg(Arg::with_name("aggregate")
+            .help("statistic of y values at the same x in each series.")
+            .long("aggregate")
+            .takes_value(true)
+            .possible_values(&["mean", "median"]))
+        .arg(Arg::with_name("spread")
+            .help("range around aggregated values. (error bars for yerrorbars, band otherwise)")
+            .long("spread")
+            .takes_value(true)
+            .requires("aggregate")
+            .possible_values(&["std", "sem", "ci95", "minmax"]))
+        .arg(Arg::with_name("max_points")
+            .help("decimate each series to at most this number of points before plotting.")
+            .long("max-points")
+            .takes_value(true)
+            .validator(max_points_validator))
+        .arg(Arg::with_name("downsample")
+            .help("decimation method used with --max-points.")
+            .long("downsample")
+            .takes_value(true)
+            .possible_values(&["lttb", "minmax"])
+            .default_value("lttb"))
         .arg(Arg::with_name("file")
             .help("output only script file. (without figure file)")
             .short("f")
             .long("file")
             .takes_value(false)
             .multiple(false))
+        .arg(Arg::with_name("theme")
+            .help("bundled font, canvas size, line width, grid, border, key box and color cycle, \
+                   overridden by other options.")
+            .long("theme")
+            .takes_value(true)
+            .possible_values(&theme::THEMES)
+            .default_value("paper"))
+        .arg(Arg::with_name("size")
+            .help("canvas size with unit in, cm or px. (ex. 3.5in,2.5in) [default: by theme]")
+            .long("size")
+            .takes_value(true)
+            .validator(size_validator))
+        .arg(Arg::with_name("dpi")
+            .help("resolution of raster output (.png), which scales fonts and lines, and of px \
+                   in --size.")
+            .long("dpi")
+            .takes_value(true)
+            .default_value("300")
+            .validator(positive_number_validator))
+        .arg(Arg::with_name("aspect")
+            .help("ratio of height to width of plot area.")
+            .long("aspect")
+            .takes_value(true)
+            .validator(positive_number_validator))
+        .arg(Arg::with_name("square")
+            .help("square plot area.")
+            .long("square")
+            .takes_value(false)
+            .conflicts_with("aspect"))
+        .arg(Arg::with_name("font")
+            .help("font family in title and label etc.. [default: by theme]")
+            .long("font")
+            .takes_value(true))
         .arg(Arg::with_name("fontsize")
-            .help("fontsize in title and label etc..")
+            .help("fontsize in title and label etc.. [default: by theme]")
             .long("fontsize")
             .takes_value(true)
-            .default_value(if cfg!(target_os = "macos") {
-                "24"
-            } else {
-                "12"
-            })
             .validator(widths_validator));
 
     let args = app.get_matches();
-    let data_files: Vec<&str> = args.values_of("INPUTS").unwrap().collect();
+    if let Some(inspect_args) = args.subcommand_matches("inspect") {
+        inspect_files(inspect_args);
+        return;
+    }
+    let data_files = args.values_of("INPUTS")
+        .map(|it| it.map(|f| f.to_string()).collect::<Vec<_>>())
+        .unwrap_or_default();
     let is_script = args.is_present("file");
-    let output_file = args.value_of("OUTPUT")
-        .map(|out| out.to_string())
-        .unwrap_or(Regex::new(r"\.[^.]*$").unwrap().replace(data_files[0], ".pdf").into_owned());
-    let xlabel = args.value_of("xlabel").unwrap().to_string();
-    let ylabel = args.value_of("ylabel").unwrap().to_string();
+    let mut xlabel = args.value_of("xlabel").unwrap().to_string();
+    let mut ylabel = args.value_of("ylabel").unwrap().to_string();
+    let x_time = args.value_of("xtime").map(TimeFormat::new);
+    let x_format = args.value_of("xformat").map(tics::tic_format);
+    let skip_rows = args.value_of("skip_rows").unwrap().parse::<usize>().unwrap();
+    let comment = args.value_of("comment").map(|c| c.to_string());
+    let missing = args.value_of("missing").map(|m| m.to_string());
+    let decimal_comma = args.is_present("decimal_comma");
     let axes = args.values_of("axes")
         .unwrap()
         .map(|it| {
-            it.split(",")
-                .map(|s| s.split(":").map(|k| k.parse::<u32>().unwrap()).collect::<Vec<_>>())
+            expr::split_top_level(it, ',')
+                .iter()
+                .map(|s| expr::parse_axes(s).unwrap())
                 .collect::<Vec<_>>()
         })
         .collect::<Vec<_>>();
-    let titles = args.values_of("titles")
+    let plots = args.values_of("plots")
+        .map(|it| it.map(|p| plot_spec(p).unwrap()).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if !plots.is_empty() && args.occurrences_of("axes") > 0 {
+        exit_with_error("--axis cannot be used with --plot.",
+                        clap::ErrorKind::ArgumentConflict);
+    }
+    let join = args.value_of("join").map(|k| Expr::new(k).unwrap());
+    let criterion = args.value_of("criterion");
+    let mut imported_files: Vec<Temp> = Vec::new();
+    let mut imported_titles = Vec::new();
+    let sources = if let Some(directory) = criterion {
+        let directory = path::Path::new(directory);
+        let estimate = Estimate::new(args.value_of("estimate").unwrap()).unwrap();
+        let benchmarks = criterion::read_benchmarks(directory, &estimate).unwrap_or_else(|e| {
+            exit_with_error(format!("failed to read criterion results in {}: {}",
+                                    directory.display(),
+                                    e)
+                                .as_str(),
+                            clap::ErrorKind::Io)
+        });
+        if benchmarks.is_empty() {
+            exit_with_error(format!("no benchmark results are found in {}.", directory.display())
+                                .as_str(),
+                            clap::ErrorKind::InvalidValue);
+        }
+        // results are written next to the output, not to be left in the criterion directory
+        let output_directory = path::Path::new(args.value_of("OUTPUT").unwrap_or("criterion.pdf"))
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or_else(|| path::Path::new("."))
+            .to_path_buf();
+        criterion::series(&benchmarks)
+            .into_iter()
+            .map(|(title, columns)| {
+                let imported = data::write_columns(&output_directory,
+                                                   &Delimiter::Char(','),
+                                                   &columns)
+                    .expect("failed to write criterion results. ");
+                let imported_path = imported.as_ref().to_str().unwrap().to_string();
+                imported_files.push(imported);
+                imported_titles.push(title);
+                (imported_path, (1..5).map(Expr::Column).collect())
+            })
+            .collect::<Vec<_>>()
+    } else if join.is_some() {
+        if !plots.is_empty() {
+            exit_with_error("--join cannot be used with --plot.",
+                            clap::ErrorKind::ArgumentConflict);
+        }
+        if data_files.len() < 2 {
+            exit_with_error("--join needs two or more input files.",
+                            clap::ErrorKind::TooFewValues);
+        }
+        // series refer to the joined file, which replaces the first input later
+        axes.concat().into_iter().map(|ax| (data_files[0].clone(), ax)).collect::<Vec<_>>()
+    } else {
+        series_sources(&data_files, &axes, &plots)
+            .unwrap_or_else(|e| exit_with_error(e.as_str(), clap::ErrorKind::WrongNumberOfValues))
+    };
+    if join.is_none() && sources.iter().any(|(_, ax)| ax.iter().any(|a| a.has_input())) {
+        exit_with_error("columns of N-th input (ex. $2@2) can be used only with --join.",
+                        clap::ErrorKind::InvalidValue);
+    }
+    let first_file = sources[0].0.clone();
+    let output_file = args.value_of("OUTPUT")
+        .map(|out| out.to_string())
+        .or_else(|| criterion.map(|_| "criterion.pdf".to_string()))
+        .unwrap_or(Regex::new(r"\.[^.]*(\.(gz|zst|xz))?$")
+            .unwrap()
+            .replace(first_file.as_str(), ".pdf")
+            .into_owned());
+    let mut titles = args.values_of("titles")
         .unwrap()
         .map(|t| t.to_string())
         .collect::<Vec<_>>();
-    let colors = args.values_of("colors").unwrap().collect::<Vec<_>>();
-    let series_types = args.values_of("seriestypes")
-        .unwrap()
-        .map(|it| match it {
-            "l" => SeriesType::Line,
-            "p" => SeriesType::Point,
-            "y" => SeriesType::YERRORBAR,
-            _ => unreachable!(),
+    if criterion.is_some() && args.occurrences_of("titles") == 0 {
+        titles = imported_titles;
+    }
+    if criterion.is_some() && args.occurrences_of("ylabel") == 0 {
+        ylabel = "time (ns)".to_string();
+    }
+    let group_by = args.value_of("group_by").map(|g| Expr::new(g).unwrap());
+    let is_cycled = group_by.is_some() || criterion.is_some() ||
+                    args.is_present("distinct_types");
+    let theme = Theme::new(args.value_of("theme").unwrap()).unwrap();
+    // series past the first cycle of mono types are gray
+    let is_mono = args.is_present("mono");
+    let colors = match (args.values_of("colors"), args.value_of("palette")) {
+        (Some(it), _) => it.flat_map(|c| expr::split_top_level(c, ',')).collect::<Vec<_>>(),
+        (None, Some(p)) => Palette::new(p).unwrap().colors(),
+        (None, None) if is_mono => {
+            ["black", "grey50"]
+                .iter()
+                .flat_map(|c| vec![c.to_string(); MONO_TYPES.len()])
+                .collect()
+        }
+        (None, None) if is_cycled && theme.colors.len() == 1 => {
+            theme::GROUP_COLORS.iter().map(|c| c.to_string()).collect()
+        }
+        (None, None) => theme.colors.iter().map(|c| c.to_string()).collect(),
+    };
+    let series_types = if criterion.is_some() && args.occurrences_of("seriestypes") == 0 {
+        vec![SeriesType::YERRORBAR]
+    } else {
+        args.values_of("seriestypes")
+            .unwrap()
+            .map(|it| match it {
+                "l" => SeriesType::Line,
+                "p" => SeriesType::Point,
+                "y" => SeriesType::YERRORBAR,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let widths = args.values_of("widths")
+        .map(|it| it.map(|w| w.parse::<f32>().unwrap()).collect::<Vec<_>>())
+        .unwrap_or_else(|| vec![theme.line_width]);
+    let linetypes = if is_mono && args.occurrences_of("linetypes") == 0 {
+        MONO_TYPES.iter()
+            .map(|&(dash, point)| {
+                LineType {
+                    dash: LineType::new(dash).unwrap().dash,
+                    point: LineType::new(point).unwrap().point,
+                }
+            })
+            .collect::<Vec<_>>()
+    } else if is_cycled && args.occurrences_of("linetypes") == 0 {
+        (1..6).map(LineType::from).collect::<Vec<_>>()
+    } else {
+        args.values_of("linetypes")
+            .unwrap()
+            .flat_map(|l| expr::split_top_level(l, ','))
+            .map(|l| LineType::new(l.as_str()).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let smooths = args.values_of("smooths")
+        .map(|it| it.map(Smooth::new).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let trends = args.values_of("trends")
+        .map(|it| it.map(Trend::new).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let statistic = args.value_of("aggregate").and_then(Aggregate::new);
+    let spread = args.value_of("spread").and_then(Spread::new);
+    let max_points = args.value_of("max_points").map(|n| n.parse::<usize>().unwrap());
+    let downsample = Downsample::new(args.value_of("downsample").unwrap()).unwrap();
+    let font = format!("{}, {}",
+                       args.value_of("font").unwrap_or(theme.font_family),
+                       args.value_of("fontsize")
+                           .map(|s| s.to_string())
+                           .unwrap_or_else(|| theme.font_size.to_string()));
+    let data_directory = path::Path::new(first_file.as_str()).parent().unwrap().to_path_buf();
+    let mut derived_files: Vec<Temp> = imported_files;
+    // compressed, binary and database inputs are converted to delimited text
+    let query = args.value_of("query").map(|q| q.to_string());
+    let conversion = input::Conversion {
+        binary: args.value_of("binary_format").and_then(BinaryFormat::new),
+        query: query.clone(),
+        delimiter: args.value_of("delimiter")
+            .and_then(Delimiter::new)
+            .unwrap_or(Delimiter::Char(',')),
+    };
+    let mut converted_files: HashMap<String, String> = HashMap::new();
+    let mut convert = |file: &String, derived_files: &mut Vec<Temp>| {
+        converted_files.entry(file.clone())
+            .or_insert_with(|| {
+                match input::convert(file.as_str(), &conversion, &data_directory)
+                    .unwrap_or_else(|e| {
+                        exit_with_error(format!("failed to convert {}: {}", file, e).as_str(),
+                                        clap::ErrorKind::Io)
+                    }) {
+                    Some(converted) => {
+                        let converted_path = converted.as_ref().to_str().unwrap().to_string();
+                        derived_files.push(converted);
+                        converted_path
+                    }
+                    None => file.clone(),
+                }
+            })
+            .clone()
+    };
+    let sources = sources.iter()
+        .map(|(file, ax)| (convert(file, &mut derived_files), ax.clone()))
+        .collect::<Vec<_>>();
+    let input_files = data_files.iter()
+        .map(|file| convert(file, &mut derived_files))
+        .collect::<Vec<_>>();
+    let mut data_format = DataFormat::new(Delimiter::Char(','));
+    data_format.x_time = x_time.clone();
+    data_format.skip_rows = skip_rows;
+    data_format.comment = comment.clone();
+    data_format.missing = missing.clone();
+    // gnuplot reads all data files by one separator, so inputs in other delimiters than the
+    // first one are copied in its delimiter
+    let given_delimiter = args.value_of("delimiter").and_then(Delimiter::new);
+    let detect = |file: &String, format: &DataFormat| {
+        data::detect_delimiter(file.as_str(), format).expect("failed to read input file. ")
+    };
+    data_format.delimiter = given_delimiter.clone()
+        .unwrap_or_else(|| detect(&sources[0].0, &data_format));
+    let mut file_delimiters: HashMap<String, Delimiter> = HashMap::new();
+    if given_delimiter.is_none() {
+        for file in sources.iter().map(|s| &s.0).chain(input_files.iter()) {
+            let delimiter = detect(file, &data_format);
+            if delimiter != data_format.delimiter {
+                file_delimiters.insert(file.clone(), delimiter);
+            }
+        }
+    }
+    if decimal_comma && data_format.delimiter == Delimiter::Char(',') {
+        exit_with_error("--decimal-comma cannot be used with comma delimiter.",
+                        clap::ErrorKind::ArgumentConflict);
+    }
+    let mut normalized_files: HashMap<String, String> = HashMap::new();
+    let mut normalize = |file: String, derived_files: &mut Vec<Temp>| {
+        if !decimal_comma && !file_delimiters.contains_key(&file) {
+            return file;
+        }
+        normalized_files.entry(file.clone())
+            .or_insert_with(|| {
+                let mut format = data_format.clone();
+                if let Some(delimiter) = file_delimiters.get(&file) {
+                    format.delimiter = delimiter.clone();
+                }
+                let normalized = data::normalize(file.as_str(),
+                                                 &format,
+                                                 &data_format.delimiter,
+                                                 decimal_comma,
+                                                 &data_directory)
+                    .expect("failed to read input file. ");
+                let normalized_path = normalized.as_ref().to_str().unwrap().to_string();
+                derived_files.push(normalized);
+                normalized_path
+            })
+            .clone()
+    };
+    let sources = sources.into_iter()
+        .map(|(file, ax)| (normalize(file, &mut derived_files), ax))
+        .collect::<Vec<_>>();
+    // columns of query results are plotted against the first column, and named by the results
+    let sources = if query.is_some() && plots.is_empty() && args.occurrences_of("axes") == 0 {
+        let mut expanded = Vec::new();
+        let mut names = Vec::new();
+        for (file, _) in sources {
+            let header = data::read_header(file.as_str(), &data_format)
+                .expect("failed to read input file. ");
+            if args.occurrences_of("xlabel") == 0 && xlabel.is_empty() {
+                xlabel = header.first().cloned().unwrap_or_default();
+            }
+            for (k, name) in header.iter().enumerate().skip(1) {
+                expanded.push((file.clone(), vec![Expr::Column(1), Expr::Column(k as u32 + 1)]));
+                names.push(name.clone());
+            }
+        }
+        if args.occurrences_of("ylabel") == 0 && names.len() == 1 {
+            ylabel = names[0].clone();
+        }
+        if args.occurrences_of("titles") == 0 {
+            titles = names;
+        }
+        expanded
+    } else {
+        sources
+    };
+    if sources.is_empty() {
+        exit_with_error("no columns are found to be plotted in query results.",
+                        clap::ErrorKind::InvalidValue);
+    }
+    for &(name, count) in &[("titles", titles.len()),
+                            ("smooths", smooths.len()),
+                            ("trends", trends.len())] {
+        if count > sources.len() {
+            exit_with_error(format!("{} {} are given for {} series.", count, name, sources.len())
+                                .as_str(),
+                            clap::ErrorKind::WrongNumberOfValues);
+        }
+    }
+    let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+    let sources = sources.into_iter()
+        .map(|(file, ax)| if ax.iter().any(|a| a.has_named()) {
+            let header = headers.entry(file.clone())
+                .or_insert_with(|| {
+                    data::read_header(file.as_str(), &data_format)
+                        .expect("failed to read input file. ")
+                });
+            let ax = ax.iter()
+                .map(|a| a.resolve(header))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap_or_else(|e| {
+                    exit_with_error(format!("{}: {}", file, e).as_str(),
+                                    clap::ErrorKind::InvalidValue)
+                });
+            (file, ax)
+        } else {
+            (file, ax)
         })
         .collect::<Vec<_>>();
-    let widths = args.values_of("widths")
-        .unwrap()
-        .map(|w| w.parse::<f32>().unwrap())
+    let sources = match join {
+        Some(ref key) => {
+            let files = input_files.iter()
+                .map(|f| normalize(f.clone(), &mut derived_files))
+                .collect::<Vec<_>>();
+            let file_headers = files.iter()
+                .map(|f| data::read_header(f.as_str(), &data_format))
+                .collect::<io::Result<Vec<_>>>()
+                .expect("failed to read input file. ");
+            // names are resolved only in inputs with header rows, which the joined file keeps
+            let resolve = |i: usize, e: &Expr| {
+                let header = &file_headers[i];
+                if e.has_named() && data_format.skip_rows == 0 && !data::is_header_row(header) {
+                    Err(format!("{}: column names cannot be used, as it has no header row.",
+                                data_files[i]))
+                } else {
+                    e.resolve(header).map_err(|err| format!("{}: {}", data_files[i], err))
+                }
+            };
+            let keys = (0..files.len())
+                .map(|i| {
+                    resolve(i, key)
+                        .map(|k| k.column().unwrap())
+                        .unwrap_or_else(|e| {
+                            exit_with_error(e.as_str(), clap::ErrorKind::InvalidValue)
+                        })
+                })
+                .collect::<Vec<_>>();
+            let (joined, offsets) =
+                data::join_files(&files, &keys, &data_format, &data_directory)
+                    .expect("failed to read input file. ");
+            let joined_path = joined.as_ref().to_str().unwrap().to_string();
+            derived_files.push(joined);
+            let column = |input: u32, e: &Expr| match input as usize {
+                i if i > 0 && i <= files.len() => {
+                    resolve(i - 1, e).map(|c| offsets[i - 1] + c.column().unwrap())
+                }
+                _ => {
+                    Err(format!("@{} refers to input #{}, but {} input files are given.",
+                                input,
+                                input,
+                                data_files.len()))
+                }
+            };
+            sources.into_iter()
+                .map(|(_, ax)| {
+                    let ax = ax.iter()
+                        .map(|a| a.resolve_inputs(&column))
+                        .collect::<Result<Vec<_>, _>>()
+                        .unwrap_or_else(|e| {
+                            exit_with_error(e.as_str(), clap::ErrorKind::InvalidValue)
+                        });
+                    (joined_path.clone(), ax)
+                })
+                .collect()
+        }
+        None => sources,
+    };
+    // gnuplot silently plots nothing from columns which are not in the data
+    let strict = args.is_present("strict");
+    let input_name = |file: &String| if join.is_some() {
+        data_files.join(" + ")
+    } else {
+        let file = normalized_files.iter().find(|&(_, n)| n == file).map_or(file, |(f, _)| f);
+        converted_files.iter().find(|&(_, c)| c == file).map_or(file, |(f, _)| f).clone()
+    };
+    for (i, (file, ax)) in sources.iter().enumerate() {
+        let columns = ax.iter()
+            .enumerate()
+            .flat_map(|(k, a)| {
+                let is_x = k == 0 && a.column().is_some();
+                a.columns().into_iter().map(move |c| (c, is_x))
+            })
+            .collect::<Vec<_>>();
+        let reader = io::BufReader::new(File::open(file).expect("failed to open input file. "));
+        if let Some(e) = data::check_columns(reader, &data_format, &columns, strict)
+            .expect("failed to read input file. ") {
+            exit_with_error(format!("{}: series #{}: {}", input_name(file), i + 1, e.message())
+                                .as_str(),
+                            clap::ErrorKind::InvalidValue);
+        }
+    }
+    // (index of source, data file, axes, group value) for each series
+    let mut grouped_files: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let series_sources = sources.iter()
+        .enumerate()
+        .flat_map(|(i, (file, ax))| match group_by {
+            Some(ref column) => {
+                let groups = grouped_files.entry(file.clone())
+                    .or_insert_with(|| {
+                        let header = headers.entry(file.clone())
+                            .or_insert_with(|| {
+                                data::read_header(file.as_str(), &data_format)
+                                    .expect("failed to read input file. ")
+                            });
+                        let column = column.resolve(header)
+                            .unwrap_or_else(|e| {
+                                exit_with_error(format!("{}: {}", file, e).as_str(),
+                                                clap::ErrorKind::InvalidValue)
+                            })
+                            .column()
+                            .unwrap();
+                        data::split_groups(file.as_str(), &data_format, column, &data_directory)
+                            .expect("failed to read input file. ")
+                            .into_iter()
+                            .map(|(value, group_file)| {
+                                let group_path = group_file.as_ref().to_str().unwrap().to_string();
+                                derived_files.push(group_file);
+                                (value, group_path)
+                            })
+                            .collect()
+                    });
+                groups.iter()
+                    .map(|(value, group_path)| {
+                        (i, group_path.clone(), ax.clone(), Some(value.clone()))
+                    })
+                    .collect::<Vec<_>>()
+            }
+            None => vec![(i, file.clone(), ax.clone(), None)],
+        })
         .collect::<Vec<_>>();
-    let linetypes = args.values_of("linetypes")
-        .unwrap()
-        .map(|w| w.parse::<u32>().unwrap())
+    if series_sources.is_empty() {
+        exit_with_error("no rows are found to be grouped in input files.",
+                        clap::ErrorKind::InvalidValue);
+    }
+    let is_raster = output_file.to_lowercase().ends_with(".png");
+    let terminal = if is_raster { "pngcairo" } else { "pdf" };
+    let size = args.value_of("size")
+        .and_then(Size::new)
+        .unwrap_or_else(|| Size::inches(theme.size.0, theme.size.1));
+    let dpi = args.value_of("dpi").unwrap().parse::<f64>().unwrap();
+    let mut plot_script = PlotScript::new();
+    plot_script.delimiter(data_format.delimiter.separator())
+        .font(font)
+        .terminal(terminal.to_string())
+        .size(size.terminal_size(is_raster, dpi))
+        .grid(theme.grid)
+        .border(theme.border)
+        .key_box(theme.key_box && !args.is_present("no_key_box"))
+        .x_label(xlabel)
+        .y_label(ylabel);
+    if is_raster {
+        // fonts in points and line widths are drawn as at 72 dpi
+        plot_script.scale(canvas::raster_scale(dpi));
+    }
+    if let Some(position) = args.values_of("key") {
+        if position.len() > 1 && position.clone().any(|p| p == "off") {
+            exit_with_error("--key off cannot be used with other positions.",
+                            clap::ErrorKind::ArgumentConflict);
+        }
+        plot_script.legend(position.map(|p| p.to_string()).collect());
+    }
+    if let Some(columns) = args.value_of("key_columns") {
+        plot_script.key_option(format!("horizontal maxcols {}", columns));
+    }
+    if let Some(font) = args.value_of("key_font") {
+        plot_script.key_option(format!("font \"{}\"", font));
+    }
+    if args.is_present("key_reverse") {
+        plot_script.key_option("reverse Left".to_string());
+    }
+    if let Some(spacing) = args.value_of("key_spacing") {
+        plot_script.key_option(format!("spacing {}", spacing));
+    }
+    if let Some(title) = args.value_of("figure_title") {
+        plot_script.title(title.to_string());
+    }
+    if args.is_present("grid") {
+        let grid_tics = args.values_of("grid")
+            .map(|it| it.map(|t| format!("{}tics", t)).collect::<Vec<_>>())
+            .unwrap_or_default();
+        plot_script.grid_tics(grid_tics);
+    }
+    if let Some(style) = args.value_of("grid_style") {
+        plot_script.grid_style(style.to_string());
+    }
+    if let Some(f) = args.value_of("yformat") {
+        plot_script.y_format(tics::tic_format(f));
+    }
+    // minor grid lines need minor tics
+    let grid_values = args.values_of("grid").map(|it| it.collect::<Vec<_>>()).unwrap_or_default();
+    let axis_tics = |axis: &str| {
+        AxisTics {
+            major: args.value_of(format!("{}tics", axis)).and_then(Tics::new),
+            minor: args.value_of(format!("m{}tics", axis))
+                .map(|n| n.parse::<u32>().unwrap())
+                .or_else(|| {
+                    if args.is_present(format!("m{}tics", axis)) ||
+                       grid_values.contains(&format!("m{}", axis).as_str()) {
+                        Some(0)
+                    } else {
+                        None
+                    }
+                }),
+            rotate: args.value_of(format!("{}tics_rotate", axis))
+                .map(|r| r.parse::<f64>().unwrap()),
+        }
+    };
+    plot_script.x_tics(axis_tics("x")).y_tics(axis_tics("y"));
+    if let Some(aspect) = args.value_of("aspect") {
+        plot_script.size_ratio(format!("ratio {}", aspect));
+    }
+    if args.is_present("square") {
+        plot_script.size_ratio("square".to_string());
+    }
+    if let Some((background, foreground)) = theme.ink {
+        plot_script.ink(Color::new(background.to_string()), Color::new(foreground.to_string()));
+    }
+    if let Some(c) = comment {
+        plot_script.comment(c);
+    }
+    if let Some(m) = missing {
+        plot_script.missing(m);
+    }
+    if let Some(t) = x_time.clone() {
+        plot_script.x_time(t);
+    }
+    if let Some(f) = x_format {
+        plot_script.x_format(f);
+    }
+    let labels = args.values_of("label").map(|it| it.collect::<Vec<_>>()).unwrap_or_default();
+    let mut annotations = labels.chunks(3)
+        .map(|l| ("label", l.join(" ")))
+        .chain(annotation::KINDS[1..]
+            .iter()
+            .flat_map(|&kind| {
+                args.values_of(kind)
+                    .into_iter()
+                    .flatten()
+                    .map(move |v| (kind, v.to_string()))
+            }))
+        .map(|(kind, spec)| {
+            Annotation::new(kind, spec.as_str(), x_time.as_ref()).unwrap_or_else(|| {
+                exit_with_error(format!("invalid {} \"{}\". (see --help for its form)",
+                                        kind,
+                                        spec)
+                                    .as_str(),
+                                clap::ErrorKind::InvalidValue)
+            })
+        })
         .collect::<Vec<_>>();
-    let fontsize = args.value_of("fontsize").unwrap().to_string();
-    let script = axes.iter()
-        .zip(data_files.iter())
-        .map(|(ax, &data)| std::iter::repeat(data).zip(ax.into_iter()))
-        .flat_map(|it| it)
-        .zip(titles.into_iter().chain((0..).map(|_| "".to_string())))
-        .zip(series_types.into_iter().cycle())
-        .zip(widths.into_iter().cycle())
-        .zip(colors.into_iter().cycle())
-        .zip(linetypes.into_iter().cycle())
-        .map(|((((((d, a), t), s), w), c), lt)| {
-            Series::new(d.to_string(),
-                        t,
-                        (a[0], a[1]), //(x,y)
-                        a.get(2).map(|&y_error| y_error),
-                        s,
-                        w,
-                        Color::new(c.to_string()),
-                        lt)
+    if let Some(file) = args.value_of("annotations") {
+        let read = File::open(file)
+            .map_err(annotation::ReadError::from)
+            .and_then(|f| annotation::read(io::BufReader::new(f), x_time.as_ref()));
+        annotations.extend(read.unwrap_or_else(|e| {
+            let kind = match e {
+                annotation::ReadError::Io(_) => clap::ErrorKind::Io,
+                annotation::ReadError::Invalid(..) => clap::ErrorKind::InvalidValue,
+            };
+            exit_with_error(format!("{}: {}", file, e.message()).as_str(), kind)
+        }));
+    }
+    for a in &annotations {
+        plot_script.annotation(annotation_command(a)
+            .unwrap_or_else(|e| exit_with_error(e.as_str(), clap::ErrorKind::InvalidValue)));
+    }
+    // included scripts are copied, so that the script written by -f reproduces the figure
+    for file in args.values_of("include").into_iter().flatten() {
+        let mut commands = String::new();
+        File::open(file)
+            .and_then(|mut f| f.read_to_string(&mut commands))
+            .unwrap_or_else(|e| {
+                exit_with_error(format!("failed to read included gnuplot script {}: {}", file, e)
+                                    .as_str(),
+                                clap::ErrorKind::Io)
+            });
+        plot_script.pre(commands.trim_end().to_string());
+    }
+    for c in args.values_of("pre").into_iter().flatten() {
+        plot_script.pre(c.to_string());
+    }
+    for c in args.values_of("post").into_iter().flatten() {
+        plot_script.post(c.to_string());
+    }
+    let series_indices = |name: &str| {
+        args.values_of(name)
+            .map(|it| it.map(|n| n.parse::<usize>().unwrap()).collect::<Vec<_>>())
+            .unwrap_or_default()
+    };
+    let hidden = series_indices("key_hide");
+    series_check("key-hide", series_sources.len(), &hidden)
+        .unwrap_or_else(|e| exit_with_error(e.as_str(), clap::ErrorKind::InvalidValue));
+    let order = key_order(series_sources.len(), &series_indices("key_order"))
+        .unwrap_or_else(|e| exit_with_error(e.as_str(), clap::ErrorKind::InvalidValue));
+    let mut row_counts = HashMap::new();
+    // colors and linetypes cycle over series, other options are given for each source
+    let mut plotted = series_sources.iter()
+        .enumerate()
+        .map(|(k, (i, d, a, g))| {
+            let t = match (titles.get(*i).filter(|t| !t.is_empty()), g) {
+                _ if hidden.contains(&(k + 1)) => String::new(),
+                (Some(t), Some(g)) => format!("{} {}", t, g),
+                (_, Some(g)) => g.clone(),
+                (t, None) => t.cloned().unwrap_or_default(),
+            };
+            let mut series = Series::new(d.to_string(),
+                                         t,
+                                         (a[0].clone(), a[1].clone()), //(x,y)
+                                         a.get(2).filter(|_| a.len() == 3).cloned(),
+                                         series_types[i % series_types.len()].clone(),
+                                         widths[i % widths.len()],
+                                         Color::new(colors[k % colors.len()].to_string()),
+                                         linetypes[k % linetypes.len()].clone());
+            if a.len() == 4 {
+                series.y_range(a[2].clone(), a[3].clone()); // (x,y,y_low,y_high)
+            }
+            let sm = smooths.get(*i).cloned().unwrap_or(None);
+            let tr = trends.get(*i).cloned().unwrap_or(None);
+            if series.s_type == SeriesType::YERRORBAR && statistic.is_some() {
+                if spread.is_none() {
+                    exit_with_error("--spread must be given for yerrorbars with --aggregate.",
+                                    clap::ErrorKind::MissingRequiredArgument);
+                }
+            } else if series.s_type == SeriesType::YERRORBAR && series.y_range.is_none() &&
+                      series.y_error_axis().is_none() {
+                exit_with_error("y error column must be given for yerrorbars with y expression.",
+                                clap::ErrorKind::WrongNumberOfValues);
+            }
+            if skip_rows > 0 {
+                series.skip(skip_rows);
+            }
+            if let Some(scale) = x_time.as_ref().and_then(|t| t.scale()) {
+                series.x_time_scale(scale);
+            }
+            if let Some(ref statistic) = statistic {
+                derived_files.push(series.aggregate(statistic,
+                               spread.as_ref(),
+                               &data_format,
+                               &data_directory)
+                    .unwrap_or_else(|e| {
+                        exit_with_error(&format!("failed to aggregate {}: {}",
+                                                 series.data_file,
+                                                 e),
+                                        clap::ErrorKind::Io)
+                    }));
+            }
+            if let Some(sm) = sm {
+                series.smooth(sm);
+            }
+            if let Some(tr) = tr {
+                derived_files.push(series.trend(&tr, &data_format, &data_directory)
+                    .unwrap_or_else(|e| {
+                        exit_with_error(&format!("failed to compute trend from {}: {}",
+                                                 series.data_file,
+                                                 e),
+                                        clap::ErrorKind::Io)
+                    }));
+            }
+            if let Some(max_points) = max_points {
+                // rows are counted once for each file, which may be shared by series
+                let rows = *row_counts.entry(series.data_file.clone()).or_insert_with(|| {
+                    File::open(series.data_file.as_str())
+                        .and_then(|f| data::count_rows(io::BufReader::new(f), &data_format))
+                        .unwrap_or_else(|e| {
+                            exit_with_error(&format!("failed to read {}: {}", series.data_file, e),
+                                            clap::ErrorKind::Io)
+                        })
+                });
+                derived_files.extend(series.downsample(&downsample,
+                                max_points,
+                                rows,
+                                &data_format,
+                                &data_directory)
+                    .unwrap_or_else(|e| {
+                        exit_with_error(&format!("failed to downsample {}: {}",
+                                                 series.data_file,
+                                                 e),
+                                        clap::ErrorKind::Io)
+                    }));
+            }
+            Some(series)
         })
-        .fold(PlotScript::new()
-                  .delimiter(",".to_string())
-                  .font(format!("Times New Roman, {}", fontsize))
-                  .x_label(xlabel)
-                  .y_label(ylabel),
-              |plt, ser| plt.plot(ser))
+        .collect::<Vec<_>>();
+    // key entries are in the order of plot commands
+    let script = order.iter()
+        .filter_map(|&k| plotted[k].take())
+        .fold(&mut plot_script, |plt, ser| plt.plot(ser))
         .finalize(output_file.clone());
 
     if is_script {
         let script_file =
             Regex::new(r"\.[^.]*$").unwrap().replace(output_file.as_str(), ".gplot").into_owned(); // replacement of extension(suffix) in filename
         File::create(script_file).unwrap().write_all(script.as_bytes()).unwrap();
+        for derived in derived_files.iter_mut() {
+            derived.release(); // derived data is referred by the script file
+        }
 
     } else {
 
-        let temp_file = Temp::new_file_in(&(path::Path::new(data_files[0]).parent().unwrap()))
-            .unwrap();
+        let temp_file = Temp::new_file_in(&data_directory).unwrap();
         let tmp_path = temp_file.as_ref().to_path_buf();
         let tmp_path = tmp_path.as_path().to_str().unwrap();
         let written = File::create(temp_file.as_ref()).unwrap().write_all(script.as_bytes());
@@ -559,10 +2364,84 @@ fn axes_validatior_test() {
     assert!(axes_validator("1:2:".to_string()).is_err());
 }
 #[test]
+fn plot_spec_test() {
+    let columns = |cs: Vec<u32>| cs.into_iter().map(Expr::Column).collect::<Vec<_>>();
+    assert_eq!(plot_spec("data.csv:1:2"),
+               Some(("data.csv".to_string(), columns(vec![1, 2]))));
+    assert_eq!(plot_spec("@2:1:3:4"),
+               Some(("@2".to_string(), columns(vec![1, 3, 4]))));
+    assert_eq!(plot_spec("C:\\data\\a.csv:1:2"),
+               Some(("C:\\data\\a.csv".to_string(), columns(vec![1, 2]))));
+    assert_eq!(plot_spec("data.csv:time:($2/1000)").map(|(f, ax)| (f, ax[1].to_script())),
+               Some(("data.csv".to_string(), "($2/1000.0)".to_string())));
+    assert_eq!(plot_spec("data:1:2:3").map(|(f, ax)| (f, ax.len())),
+               Some(("data".to_string(), 3)));
+    assert_eq!(plot_spec("data.csv:1"), None);
+    assert_eq!(plot_spec("1:2"), None);
+    assert!(plot_spec_validator("a.csv:x-1:2".to_string()).is_err());
+}
+#[test]
+fn series_sources_test() {
+    let inputs = vec!["a.csv".to_string(), "b.csv".to_string()];
+    let source = |f: &str, ax: Vec<u32>| {
+        (f.to_string(), ax.into_iter().map(Expr::Column).collect::<Vec<_>>())
+    };
+    let group = |ax: Vec<Vec<u32>>| {
+        ax.into_iter()
+            .map(|a| a.into_iter().map(Expr::Column).collect::<Vec<_>>())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(series_sources(&inputs,
+                              &[group(vec![vec![1, 2], vec![1, 3]]), group(vec![vec![2, 4]])],
+                              &[]),
+               Ok(vec![source("a.csv", vec![1, 2]),
+                       source("a.csv", vec![1, 3]),
+                       source("b.csv", vec![2, 4])]));
+    assert_eq!(series_sources(&inputs, &[group(vec![vec![1, 2]])], &[]),
+               Ok(vec![source("a.csv", vec![1, 2]), source("b.csv", vec![1, 2])]));
+    assert!(series_sources(&inputs[..1],
+                           &[group(vec![vec![1, 2]]), group(vec![vec![1, 3]])],
+                           &[])
+        .is_err());
+    assert!(series_sources(&[inputs[0].clone(), inputs[1].clone(), "c.csv".to_string()],
+                           &[group(vec![vec![1, 2]]), group(vec![vec![1, 3]])],
+                           &[])
+        .is_err());
+    let plots = vec![source("@2", vec![1, 3]),
+                     source("c.csv", vec![1, 2]),
+                     source("@1", vec![1, 2])];
+    assert_eq!(series_sources(&inputs, &[group(vec![vec![1, 2]])], &plots),
+               Ok(vec![source("b.csv", vec![1, 3]),
+                       source("c.csv", vec![1, 2]),
+                       source("a.csv", vec![1, 2])]));
+    assert!(series_sources(&inputs, &[group(vec![vec![1, 2]])], &plots[..2]).is_err());
+    assert!(series_sources(&[], &[group(vec![vec![1, 2]])], &plots[..1]).is_err());
+    assert_eq!(series_sources(&[], &[group(vec![vec![1, 2]])], &plots[1..2]),
+               Ok(vec![source("c.csv", vec![1, 2])]));
+    let plots = vec![source("a.csv", vec![1, 2]), source("@2", vec![1, 3])];
+    assert_eq!(series_sources(&inputs, &[group(vec![vec![1, 2]])], &plots),
+               Ok(vec![source("a.csv", vec![1, 2]), source("b.csv", vec![1, 3])]));
+}
+#[test]
 fn colors_validator_test() {
     assert!(colors_validator("red,f8Ab05".to_string()).is_ok());
     assert!(colors_validator("lered,aaaagg".to_string()).is_err());
-    assert!(colors_validator("#ABCDEF".to_string()).is_err());
+    assert!(colors_validator("#ABCDEF,#abc,#80ff0000".to_string()).is_ok());
+    assert!(colors_validator("rgba(255,0,0,0.5),hsv(120,1,0.5)".to_string()).is_ok());
+    assert!(colors_validator("dark-yellow,medium-blue,light-grey".to_string()).is_ok());
+    assert!(colors_validator("radk-yellow".to_string()).is_err());
+    assert!(colors_validator("rgb(256,0,0)".to_string()).is_err());
+    assert!(colors_validator("#abcd".to_string()).is_err());
+}
+#[test]
+fn key_order_test() {
+    assert_eq!(key_order(4, &[]), Ok(vec![0, 1, 2, 3]));
+    assert_eq!(key_order(4, &[3, 1]), Ok(vec![2, 0, 1, 3]));
+    assert_eq!(key_order(2, &[2, 2]), Ok(vec![1, 0]));
+    assert!(key_order(2, &[3]).is_err());
+    assert!(series_check("key-hide", 2, &[1, 2]).is_ok());
+    assert_eq!(series_check("key-hide", 2, &[3]),
+               Err("--key-hide refers to series #3, but 2 series are plotted.".to_string()));
 }
 #[test]
 fn widths_validator_test() {
@@ -574,6 +2453,11 @@ fn widths_validator_test() {
 fn linetypes_validator_test() {
     assert!(linetypes_validator("1,10,50".to_string()).is_ok());
     assert!(linetypes_validator("10,-5,50".to_string()).is_err());
+    assert!(linetypes_validator("(10,5,2,5),circle,square-open".to_string()).is_ok());
+    assert!(linetypes_validator("(10,5,2)".to_string()).is_err());
+    assert!(linetypes_validator("(1,2,3,4,5,6,7,8)".to_string()).is_ok());
+    assert!(linetypes_validator("(1,2,3,4,5,6,7,8,9,10)".to_string()).is_err());
+    assert!(linetypes_validator("hexagon".to_string()).is_err());
 }
 #[test]
 fn path_split_escaper_test() {
@@ -598,16 +2482,22 @@ fn line_specifier_test() {
 }
 #[test]
 fn linetype_specifier_test() {
-    assert_eq!(SeriesType::Line.linetype_specifier(1), "dt 1".to_string());
-    assert_eq!(SeriesType::Point.linetype_specifier(1), "pt 1".to_string());
-    assert_eq!(SeriesType::YERRORBAR.linetype_specifier(1),
+    assert_eq!(SeriesType::Line.linetype_specifier(&LineType::from(1)), "dt 1".to_string());
+    assert_eq!(SeriesType::Point.linetype_specifier(&LineType::from(1)), "pt 1".to_string());
+    assert_eq!(SeriesType::YERRORBAR.linetype_specifier(&LineType::from(1)),
                "pt 1".to_string());
-    assert_eq!(SeriesType::Line.linetype_specifier(100),
+    assert_eq!(SeriesType::Line.linetype_specifier(&LineType::from(100)),
                "dt 100".to_string());
-    assert_eq!(SeriesType::Point.linetype_specifier(100),
+    assert_eq!(SeriesType::Point.linetype_specifier(&LineType::from(100)),
                "pt 100".to_string());
-    assert_eq!(SeriesType::YERRORBAR.linetype_specifier(100),
+    assert_eq!(SeriesType::YERRORBAR.linetype_specifier(&LineType::from(100)),
                "pt 100".to_string());
+    let dashed = LineType::new("(10,5,2,5)").unwrap();
+    assert_eq!(SeriesType::Line.linetype_specifier(&dashed),
+               "dt (10,5,2,5)".to_string());
+    assert_eq!(SeriesType::Point.linetype_specifier(&dashed), "pt 1".to_string());
+    assert_eq!(SeriesType::Point.linetype_specifier(&LineType::new("triangle").unwrap()),
+               "pt 9".to_string());
 }
 #[test]
 fn color_new_test() {
@@ -615,6 +2505,14 @@ fn color_new_test() {
                Color::Name("blue".to_string()));
     assert_eq!(Color::new("99ab55".to_string()),
                Color::Code("99ab55".to_string()));
+    assert_eq!(Color::new("#f0a".to_string()), Color::Code("ff00aa".to_string()));
+    assert_eq!(Color::new("#40FF0000".to_string()), Color::Code("40FF0000".to_string()));
+    assert_eq!(Color::new("rgba(255, 128, 0, 0.25)".to_string()),
+               Color::Code("bfff8000".to_string()));
+    assert_eq!(Color::new("hsv(240,1,1)".to_string()), Color::Code("0000ff".to_string()));
+    assert_eq!(Color::new("hsv(-60,1,1,1)".to_string()), Color::Code("ff00ff".to_string()));
+    assert_eq!(Color::new("dark-grey99ab55".to_string()),
+               Color::Name("dark-grey99ab55".to_string()));
 }
 #[test]
 fn color_specifier_test() {
@@ -660,7 +2558,7 @@ fn series_to_plot_test() {
     let series = Series::new("hoge.csv".to_string(),
                              "".to_string(),
                              (10, 5),
-                             Some(11),
+                             Some(Expr::Column(11)),
                              SeriesType::YERRORBAR,
                              1.0,
                              Color::new("afBF55".to_string()),
@@ -670,11 +2568,104 @@ fn series_to_plot_test() {
                    .to_string());
 }
 #[test]
+fn smooth_test() {
+    assert_eq!(Smooth::new("bezier"), Some(Smooth::Bezier));
+    assert_eq!(Smooth::new("none"), None);
+    assert_eq!(Smooth::Kdensity.specifier(), "smooth kdensity".to_string());
+    let mut series = Series::new("test.csv".to_string(),
+                                 "raw".to_string(),
+                                 (1, 2),
+                                 None,
+                                 SeriesType::Line,
+                                 1.5,
+                                 Color::new("red".to_string()),
+                                 1);
+    series.smooth(Smooth::Csplines);
+    assert_eq!(series.to_script(),
+               "\"test.csv\" using 1:2 smooth csplines title \"raw\" with line lw 1.5 lc \"red\" \
+                dt 1"
+                   .to_string());
+}
+#[test]
+fn y_range_test() {
+    let mut series = Series::new("test.csv".to_string(),
+                                 "".to_string(),
+                                 (1, 2),
+                                 None,
+                                 SeriesType::Line,
+                                 1.0,
+                                 Color::new("red".to_string()),
+                                 1);
+    series.y_range(Expr::Column(3), Expr::Column(4));
+    assert_eq!(series.to_script(),
+               "\"test.csv\" using 1:3:4 notitle with filledcurves fc \"red\" fs transparent \
+                solid 0.25 noborder, \"test.csv\" using 1:2 notitle with line lw 1 lc \"red\" dt 1"
+                   .to_string());
+    series.s_type = SeriesType::YERRORBAR;
+    assert_eq!(series.to_script(),
+               "\"test.csv\" using 1:2:3:4 notitle with yerrorbars ps 1 lc \"red\" pt 1"
+                   .to_string());
+}
+#[test]
+fn trends_validator_test() {
+    assert!(trends_validator("none,ma:5,ema:0.2,loess:0.3".to_string()).is_ok());
+    assert!(trends_validator("ma:-1".to_string()).is_err());
+    assert!(trends_validator("bezier".to_string()).is_err());
+}
+#[test]
+fn max_points_validator_test() {
+    assert!(max_points_validator("1000".to_string()).is_ok());
+    assert!(max_points_validator("2".to_string()).is_err());
+    assert!(max_points_validator("1e6".to_string()).is_err());
+}
+#[test]
+fn x_time_test() {
+    let mut series = Series::new("test.csv".to_string(),
+                                 "".to_string(),
+                                 (1, 2),
+                                 None,
+                                 SeriesType::Line,
+                                 1.5,
+                                 Color::new("red".to_string()),
+                                 1);
+    series.x_time_scale(1000);
+    assert_eq!(series.to_script(),
+               "\"test.csv\" using ($1/1000.0):2 notitle with line lw 1.5 lc \"red\" dt 1"
+                   .to_string());
+    let mut script = PlotScript::new();
+    script.x_time(TimeFormat::new("%Y-%m-%d %H:%M")).x_format("%H:%M".to_string());
+    assert!(script.finalize(String::from("hoge.pdf"))
+        .contains("set ylabel \"\"\nset xdata time\nset timefmt \"%Y-%m-%d %H:%M\"\nset format x \
+                   \"%H:%M\"\nset output"));
+}
+#[test]
+fn datafile_test() {
+    let mut series = Series::new("test.csv".to_string(),
+                                 "".to_string(),
+                                 (1, 2),
+                                 None,
+                                 SeriesType::Line,
+                                 1.5,
+                                 Color::new("red".to_string()),
+                                 1);
+    series.skip(2);
+    assert_eq!(series.to_script(),
+               "\"test.csv\" skip 2 using 1:2 notitle with line lw 1.5 lc \"red\" dt 1"
+                   .to_string());
+    let mut script = PlotScript::new();
+    script.delimiter("whitespace".to_string())
+        .comment("%".to_string())
+        .missing("NA".to_string());
+    assert!(script.finalize(String::from("hoge.pdf"))
+        .contains("set datafile separator whitespace\nset datafile commentschars \"%\"\nset \
+                   datafile missing \"NA\"\nset xlabel"));
+}
+#[test]
 fn finalize_without_series_test() {
     let script = PlotScript::new();
     let output = String::from("hoge.pdf");
     assert_eq!(script.finalize(output.clone()),
-               format!("set terminal pdf enhanced font \"Times New Roman, 24\"\nset datafile \
+               format!("set terminal pdf enhanced font \"Times New Roman, 12\"\nset datafile \
                         separator \"\\t\"\nset \
                         xlabel \"\"\nset ylabel \"\"\nset output {}",
                        if cfg!(target_os = "windows") {
@@ -697,7 +2688,7 @@ fn finalize_with_series_test() {
                              1);
     script.plot(series);
     assert_eq!(script.finalize(output.clone()),
-               format!("set terminal pdf enhanced font \"Times New Roman, 24\"\nset datafile \
+               format!("set terminal pdf enhanced font \"Times New Roman, 12\"\nset datafile \
                         separator \"\\t\"\nset \
                         xlabel \"\"\nset ylabel \"\"\nset output {}\n\nplot \"test.csv\" using \
                         1:2 notitle with line lw 1.5 lc \"red\" dt 1\nset output \"{}\"\nreplot",
@@ -717,7 +2708,7 @@ fn finalize_with_series_test() {
                               15);
     script.plot(series2);
     assert_eq!(script.finalize(output.clone()),
-               format!("set terminal pdf enhanced font \"Times New Roman, 24\"\nset datafile \
+               format!("set terminal pdf enhanced font \"Times New Roman, 12\"\nset datafile \
                         separator \"\\t\"\nset \
                         xlabel \"\"\nset ylabel \"\"\nset output {}\n\nplot \"test.csv\" using \
                         1:2 notitle with line lw 1.5 lc \"red\" dt 1\nreplot \"hoge.csv\" using \
@@ -742,7 +2733,7 @@ fn finalize_custom_script_test() {
     let series = Series::new("test.csv".to_string(),
                              "test".to_string(),
                              (1, 2),
-                             Some(4),
+                             Some(Expr::Column(4)),
                              SeriesType::YERRORBAR,
                              1.5,
                              Color::new("red".to_string()),
@@ -761,3 +2752,102 @@ fn finalize_custom_script_test() {
                        },
                        output.clone()));
 }
+#[test]
+fn finalize_theme_test() {
+    let mut script = PlotScript::new();
+    script.size("3.5in,2.5in".to_string())
+        .size_ratio("ratio 0.75".to_string())
+        .grid(true)
+        .border(3)
+        .key_box(false)
+        .ink(Color::new("1e1e1e".to_string()), Color::new("white".to_string()));
+    script.plot(Series::new("test.csv".to_string(),
+                            "test".to_string(),
+                            (1, 2),
+                            None,
+                            SeriesType::Line,
+                            1.0,
+                            Color::new("red".to_string()),
+                            1));
+    let finalized = script.finalize(String::from("hoge.pdf"));
+    assert!(finalized.starts_with("set terminal pdf enhanced font \"Times New Roman, 12\" size \
+                                   3.5in,2.5in background rgb \"#1e1e1e\"\n"));
+    assert!(finalized.contains("set key above textcolor \"white\"\nset key nobox\nset size \
+                                ratio 0.75\nset border 3\nset tics nomirror\nset border lc \
+                                \"white\"\nset tics textcolor \"white\"\nset grid lc \"white\" \
+                                dt 3\nset xlabel \"\" textcolor \"white\"\n"));
+}
+#[test]
+fn finalize_key_test() {
+    let mut script = PlotScript::new();
+    script.key_box(false)
+        .plot(Series::new("test.csv".to_string(),
+                          "test".to_string(),
+                          (1, 2),
+                          None,
+                          SeriesType::Line,
+                          1.0,
+                          Color::new("red".to_string()),
+                          1));
+    script.legend(vec!["outside".to_string(), "right".to_string()])
+        .key_option("horizontal maxcols 2".to_string())
+        .key_option("reverse Left".to_string());
+    assert!(script.finalize(String::from("hoge.pdf"))
+        .contains("\nset key outside right\nset key nobox\nset key horizontal maxcols 2 \
+                   reverse Left\nset xlabel"));
+    script.legend(vec!["off".to_string()]);
+    assert!(script.finalize(String::from("hoge.pdf")).contains("\nset key off\nset xlabel"));
+}
+#[test]
+fn finalize_tics_test() {
+    let mut script = PlotScript::new();
+    script.title("Latency".to_string())
+        .grid(true)
+        .grid_tics(vec!["xtics".to_string(), "mxtics".to_string()])
+        .grid_style("lc \"gray\"".to_string())
+        .y_tics(AxisTics { major: Some(Tics::Count(3)), ..AxisTics::default() })
+        .y_format("%.1s%c".to_string())
+        .plot(Series::new("test.csv".to_string(),
+                          "".to_string(),
+                          (1, 2),
+                          None,
+                          SeriesType::Line,
+                          1.0,
+                          Color::new("red".to_string()),
+                          1));
+    let finalized = script.finalize(String::from("hoge.pdf"));
+    assert!(finalized.contains("\nset title \"Latency\"\nset grid xtics mxtics lc \"gray\"\n"));
+    assert!(finalized.contains("set format y \"%.1s%c\"\n"));
+    assert!(finalized.ends_with("dt 1\nset yrange [GPVAL_Y_MIN:GPVAL_Y_MAX]\nset ytics \
+                                 GPVAL_Y_MIN, (GPVAL_Y_MAX - GPVAL_Y_MIN) / 2, GPVAL_Y_MAX\nset \
+                                 output \"hoge.pdf\"\nreplot"));
+}
+#[test]
+fn finalize_commands_test() {
+    let mut script = PlotScript::new();
+    script.annotation("set label \"a\" at first 1, first 2\n".to_string())
+        .pre("set logscale y".to_string())
+        .post("unset key".to_string())
+        .plot(Series::new("test.csv".to_string(),
+                          "".to_string(),
+                          (1, 2),
+                          None,
+                          SeriesType::Line,
+                          1.0,
+                          Color::new("red".to_string()),
+                          1));
+    let finalized = script.finalize(String::from("hoge.pdf"));
+    assert!(finalized.contains("\nset label \"a\" at first 1, first 2\nset logscale y\nset \
+                                output "));
+    assert!(finalized.ends_with("dt 1\nunset key\nset output \"hoge.pdf\"\nreplot"));
+}
+#[test]
+fn finalize_raster_test() {
+    let mut script = PlotScript::new();
+    script.terminal("pngcairo".to_string())
+        .size("1050,750".to_string())
+        .scale(canvas::raster_scale(300.0));
+    assert!(script.finalize(String::from("hoge.png"))
+        .starts_with("set terminal pngcairo enhanced font \"Times New Roman, 12\" size 1050,750 \
+                      fontscale 4.1667 linewidth 4.1667\n"));
+}
@@ -0,0 +1,85 @@
+/// named color cycle assigned to series when colors are not given.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Palette {
+    Tableau10,
+    /// colorblind-safe palette by Okabe and Ito.
+    OkabeIto,
+    /// N colors sampled evenly from viridis colormap.
+    Viridis(usize),
+    Set1,
+    Grayscale,
+}
+
+const TABLEAU10: [&str; 10] = ["4e79a7", "f28e2b", "e15759", "76b7b2", "59a14f", "edc948",
+                               "b07aa1", "ff9da7", "9c755f", "bab0ac"];
+const OKABE_ITO: [&str; 8] = ["000000", "e69f00", "56b4e9", "009e73", "f0e442", "0072b2",
+                              "d55e00", "cc79a7"];
+const SET1: [&str; 9] = ["e41a1c", "377eb8", "4daf4a", "984ea3", "ff7f00", "ffff33", "a65628",
+                         "f781bf", "999999"];
+const GRAYSCALE: [&str; 5] = ["000000", "404040", "707070", "a0a0a0", "c8c8c8"];
+/// viridis at 0, 1/8, .., 1.
+const VIRIDIS: [(u8, u8, u8); 9] = [(68, 1, 84),
+                                    (71, 44, 122),
+                                    (59, 81, 139),
+                                    (44, 113, 142),
+                                    (33, 144, 141),
+                                    (39, 173, 129),
+                                    (92, 200, 99),
+                                    (170, 220, 50),
+                                    (253, 231, 37)];
+
+impl Palette {
+    /// parse `tableau10`, `okabe-ito`, `viridis:N`, `set1` or `grayscale`.
+    pub fn new(arg: &str) -> Option<Palette> {
+        match arg {
+            "tableau10" => Some(Palette::Tableau10),
+            "okabe-ito" => Some(Palette::OkabeIto),
+            "set1" => Some(Palette::Set1),
+            "grayscale" => Some(Palette::Grayscale),
+            _ => {
+                arg.strip_prefix("viridis:")
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|&n| n > 0)
+                    .map(Palette::Viridis)
+            }
+        }
+    }
+    /// colors as `RRGGBB` codes.
+    pub fn colors(&self) -> Vec<String> {
+        let codes = |cs: &[&str]| cs.iter().map(|c| c.to_string()).collect();
+        match *self {
+            Palette::Tableau10 => codes(&TABLEAU10),
+            Palette::OkabeIto => codes(&OKABE_ITO),
+            Palette::Set1 => codes(&SET1),
+            Palette::Grayscale => codes(&GRAYSCALE),
+            Palette::Viridis(n) => {
+                (0..n)
+                    .map(|i| if n == 1 { 0.0 } else { i as f64 / (n - 1) as f64 })
+                    .map(viridis)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// color at `t` in [0, 1] of viridis, linearly interpolated.
+fn viridis(t: f64) -> String {
+    let position = t * (VIRIDIS.len() - 1) as f64;
+    let i = (position.floor() as usize).min(VIRIDIS.len() - 2);
+    let f = position - i as f64;
+    let (a, b) = (VIRIDIS[i], VIRIDIS[i + 1]);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+    format!("{:02x}{:02x}{:02x}", mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+#[test]
+fn palette_test() {
+    assert_eq!(Palette::new("okabe-ito").map(|p| p.colors().len()), Some(8));
+    assert_eq!(Palette::new("viridis:3").map(|p| p.colors()),
+               Some(vec!["440154".to_string(), "21908d".to_string(), "fde725".to_string()]));
+    assert_eq!(Palette::new("viridis:1").map(|p| p.colors()),
+               Some(vec!["440154".to_string()]));
+    assert_eq!(Palette::new("viridis:0"), None);
+    assert_eq!(Palette::new("viridis"), None);
+    assert_eq!(Palette::new("jet"), None);
+}
@@ -0,0 +1,123 @@
+/// style bundled for a kind of figure, applied before options given by user.
+#[derive(Debug,Clone,PartialEq)]
+pub struct Theme {
+    pub font_family: &'static str,
+    pub font_size: f32,
+    /// canvas (width, height) in inches.
+    pub size: (f64, f64),
+    pub line_width: f32,
+    pub grid: bool,
+    /// sides of border as gnuplot's `set border`. (15 for all, 3 for bottom and left)
+    pub border: u32,
+    pub key_box: bool,
+    /// color cycle over series.
+    pub colors: &'static [&'static str],
+    /// (background, foreground) if not black on white.
+    pub ink: Option<(&'static str, &'static str)>,
+}
+
+pub const THEMES: [&str; 7] = ["paper", "ieee-single", "ieee-double", "acm", "slides", "dark",
+                               "minimal"];
+
+const PRINT_COLORS: [&str; 1] = ["black"];
+/// color cycle of series split by `--group-by` (or `--distinct-types`) in themes of one color.
+pub const GROUP_COLORS: [&str; 8] = ["black", "red", "web-blue", "web-green", "dark-magenta",
+                                     "dark-orange", "dark-cyan", "goldenrod"];
+const TABLEAU_COLORS: [&str; 8] = ["4e79a7", "f28e2b", "e15759", "76b7b2", "59a14f", "edc948",
+                                   "b07aa1", "ff9da7"];
+const DARK_COLORS: [&str; 6] = ["8ab4f8", "f6ae2d", "81c995", "f28b82", "c58af9", "78d9ec"];
+const MINIMAL_COLORS: [&str; 5] = ["333333", "e15759", "4e79a7", "999999", "59a14f"];
+
+impl Theme {
+    pub fn new(name: &str) -> Option<Theme> {
+        let paper = Theme {
+            font_family: "Times New Roman",
+            font_size: 12.0,
+            size: (5.0, 3.0),
+            line_width: 1.0,
+            grid: false,
+            border: 15,
+            key_box: true,
+            colors: &PRINT_COLORS,
+            ink: None,
+        };
+        match name {
+            "paper" => Some(paper),
+            "ieee-single" => {
+                Some(Theme {
+                    font_size: 8.0,
+                    size: (3.5, 2.5),
+                    grid: true,
+                    key_box: false,
+                    ..paper
+                })
+            }
+            "ieee-double" => {
+                Some(Theme {
+                    font_size: 8.0,
+                    size: (7.16, 3.0),
+                    grid: true,
+                    key_box: false,
+                    ..paper
+                })
+            }
+            "acm" => {
+                Some(Theme {
+                    font_family: "Linux Libertine",
+                    font_size: 8.0,
+                    size: (3.33, 2.5),
+                    grid: true,
+                    key_box: false,
+                    ..paper
+                })
+            }
+            "slides" => {
+                Some(Theme {
+                    font_family: "Helvetica",
+                    font_size: 20.0,
+                    size: (10.0, 5.63),
+                    line_width: 3.0,
+                    grid: true,
+                    key_box: false,
+                    colors: &TABLEAU_COLORS,
+                    ..paper
+                })
+            }
+            "dark" => {
+                Some(Theme {
+                    font_family: "Helvetica",
+                    font_size: 14.0,
+                    size: (6.0, 4.0),
+                    line_width: 2.0,
+                    grid: true,
+                    key_box: false,
+                    colors: &DARK_COLORS,
+                    ink: Some(("1e1e1e", "e0e0e0")),
+                    ..paper
+                })
+            }
+            "minimal" => {
+                Some(Theme {
+                    font_family: "Helvetica",
+                    font_size: 12.0,
+                    line_width: 1.5,
+                    border: 3,
+                    key_box: false,
+                    colors: &MINIMAL_COLORS,
+                    ..paper
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn theme_test() {
+    assert!(THEMES.iter().all(|t| Theme::new(t).is_some()));
+    assert_eq!(Theme::new("ieee-single").map(|t| (t.font_family, t.size)),
+               Some(("Times New Roman", (3.5, 2.5))));
+    assert_eq!(Theme::new("minimal").map(|t| t.border), Some(3));
+    assert_eq!(Theme::new("acm").map(|t| t.colors), Some(&["black"][..]));
+    assert_eq!(Theme::new("poster"), None);
+}
@@ -0,0 +1,114 @@
+/// positions of major tics on an axis.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Tics {
+    Step(f64),
+    /// tics at these values. (ex. `1,10,100`)
+    List(Vec<f64>),
+    /// N tics evenly spaced over the range of the first plot.
+    Count(u32),
+}
+/// tic settings of an axis.
+#[derive(Debug,Clone,Default,PartialEq)]
+pub struct AxisTics {
+    pub major: Option<Tics>,
+    /// number of minor intervals between major tics. 0 for gnuplot's default.
+    pub minor: Option<u32>,
+    /// rotation of tic labels in degrees.
+    pub rotate: Option<f64>,
+}
+
+impl Tics {
+    /// parse a step (`0.5`), a list (`1,10,100`) or a count (`count:5`).
+    pub fn new(arg: &str) -> Option<Tics> {
+        if let Some(n) = arg.strip_prefix("count:") {
+            return n.parse::<u32>().ok().filter(|&n| n >= 2).map(Tics::Count);
+        }
+        let values = arg.split(',')
+            .map(|v| v.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+            .collect::<Option<Vec<_>>>()?;
+        match values.len() {
+            1 if values[0] > 0.0 => Some(Tics::Step(values[0])),
+            1 => None,
+            _ => Some(Tics::List(values)),
+        }
+    }
+}
+impl AxisTics {
+    /// `set` commands for `axis` (x or y) before plotting.
+    pub fn config(&self, axis: &str) -> String {
+        let mut config = String::new();
+        match self.major {
+            Some(Tics::Step(step)) => config += format!("set {}tics {}\n", axis, step).as_str(),
+            Some(Tics::List(ref values)) => {
+                config += format!("set {}tics ({})\n",
+                                  axis,
+                                  values.iter()
+                                      .map(|v| v.to_string())
+                                      .collect::<Vec<_>>()
+                                      .join(", "))
+                    .as_str()
+            }
+            _ => (),
+        }
+        match self.minor {
+            Some(0) => config += format!("set m{}tics\n", axis).as_str(),
+            Some(n) => config += format!("set m{}tics {}\n", axis, n).as_str(),
+            None => (),
+        }
+        if let Some(angle) = self.rotate {
+            config += format!("set {}tics rotate by {} right\n", axis, angle).as_str();
+        }
+        config
+    }
+    /// `set` commands after the first plot, which fixes the range of `axis`.
+    pub fn range_config(&self, axis: &str) -> String {
+        let upper = axis.to_uppercase();
+        match self.major {
+            Some(Tics::Count(n)) => {
+                format!("set {a}range [GPVAL_{u}_MIN:GPVAL_{u}_MAX]\nset {a}tics GPVAL_{u}_MIN, \
+                         (GPVAL_{u}_MAX - GPVAL_{u}_MIN) / {n}, GPVAL_{u}_MAX\n",
+                        a = axis,
+                        u = upper,
+                        n = n - 1)
+            }
+            _ => String::new(),
+        }
+    }
+}
+
+/// tic label format. `si` (SI prefix as 1.5k), `sci` (1.5×10^3) and `percent` are presets,
+/// others are gnuplot's formats. `percent` only appends `%`, so values must be in percent.
+pub fn tic_format(arg: &str) -> String {
+    match arg {
+        "si" => "%.1s%c".to_string(),
+        "sci" => "%.1t×10^{%T}".to_string(),
+        "percent" => "%g%%".to_string(),
+        _ => arg.to_string(),
+    }
+}
+
+#[test]
+fn tics_test() {
+    assert_eq!(Tics::new("0.5"), Some(Tics::Step(0.5)));
+    assert_eq!(Tics::new("1, 10,100"), Some(Tics::List(vec![1.0, 10.0, 100.0])));
+    assert_eq!(Tics::new("count:5"), Some(Tics::Count(5)));
+    assert_eq!(Tics::new("count:1"), None);
+    assert_eq!(Tics::new("-1"), None);
+    assert_eq!(Tics::new("a,b"), None);
+    let tics = AxisTics {
+        major: Tics::new("1,10,100"),
+        minor: Some(0),
+        rotate: Some(-45.0),
+    };
+    assert_eq!(tics.config("x"),
+               "set xtics (1, 10, 100)\nset mxtics\nset xtics rotate by -45 right\n".to_string());
+    assert_eq!(tics.range_config("x"), "".to_string());
+    let tics = AxisTics { major: Some(Tics::Count(3)), ..AxisTics::default() };
+    assert_eq!(tics.config("y"), "".to_string());
+    assert_eq!(tics.range_config("y"),
+               "set yrange [GPVAL_Y_MIN:GPVAL_Y_MAX]\nset ytics GPVAL_Y_MIN, (GPVAL_Y_MAX - \
+                GPVAL_Y_MIN) / 2, GPVAL_Y_MAX\n"
+                   .to_string());
+    assert_eq!(tic_format("si"), "%.1s%c".to_string());
+    assert_eq!(tic_format("%.2f"), "%.2f".to_string());
+}
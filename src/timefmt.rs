@@ -0,0 +1,151 @@
+/// format of time values in x column.
+#[derive(Debug,Clone,PartialEq)]
+pub enum TimeFormat {
+    Format(String),
+    EpochSeconds,
+    EpochMillis,
+}
+
+impl TimeFormat {
+    pub fn new(arg: &str) -> TimeFormat {
+        match arg {
+            "epoch" => TimeFormat::EpochSeconds,
+            "epoch-ms" => TimeFormat::EpochMillis,
+            _ => TimeFormat::Format(arg.to_string()),
+        }
+    }
+    /// format given to gnuplot's `set timefmt`.
+    pub fn timefmt(&self) -> String {
+        match *self {
+            TimeFormat::Format(ref fmt) => fmt.clone(),
+            TimeFormat::EpochSeconds | TimeFormat::EpochMillis => "%s".to_string(),
+        }
+    }
+    /// values in x column are epoch time in 1/N seconds, which gnuplot cannot read by `timefmt`.
+    pub fn scale(&self) -> Option<u32> {
+        match *self {
+            TimeFormat::EpochMillis => Some(1000),
+            _ => None,
+        }
+    }
+    /// seconds since epoch (UTC) for `Format`, raw value for epoch formats.
+    pub fn parse(&self, s: &str) -> Option<f64> {
+        match *self {
+            TimeFormat::Format(ref fmt) => strptime(s.trim(), fmt),
+            TimeFormat::EpochSeconds | TimeFormat::EpochMillis => s.trim().parse::<f64>().ok(),
+        }
+    }
+}
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep",
+                            "oct", "nov", "dec"];
+
+/// subset of strptime used by gnuplot's timefmt. (%Y %y %m %d %j %H %M %S %b %B %s %%)
+fn strptime(s: &str, fmt: &str) -> Option<f64> {
+    let (mut year, mut month, mut day, mut yday) = (1970i64, 1i64, 1i64, None);
+    let (mut hour, mut minute, mut second) = (0i64, 0i64, 0.0f64);
+    let mut epoch = None;
+    let mut input = s;
+    let mut spec = fmt.chars();
+    while let Some(c) = spec.next() {
+        if c == '%' {
+            let conversion = spec.next()?;
+            match conversion {
+                'Y' => year = take_number(&mut input, 4)?,
+                'y' => {
+                    let y = take_number(&mut input, 2)?;
+                    year = if y < 69 { 2000 + y } else { 1900 + y };
+                }
+                'm' => month = take_number(&mut input, 2)?,
+                'd' => day = take_number(&mut input, 2)?,
+                'j' => yday = Some(take_number(&mut input, 3)?),
+                'H' => hour = take_number(&mut input, 2)?,
+                'M' => minute = take_number(&mut input, 2)?,
+                'S' => {
+                    let end = input.find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                        .unwrap_or(input.len());
+                    second = input[..end].parse::<f64>().ok()?;
+                    input = &input[end..];
+                }
+                's' => {
+                    let end = input.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+                        .unwrap_or(input.len());
+                    epoch = Some(input[..end].parse::<f64>().ok()?);
+                    input = &input[end..];
+                }
+                'b' | 'B' => {
+                    let lower = input.to_lowercase();
+                    let index = MONTHS.iter().position(|m| lower.starts_with(m))?;
+                    month = index as i64 + 1;
+                    let end = input.find(|c: char| !c.is_alphabetic()).unwrap_or(input.len());
+                    input = &input[end..];
+                }
+                '%' => input = input.strip_prefix('%')?,
+                _ => return None,
+            }
+        } else if c.is_whitespace() {
+            input = input.trim_start();
+        } else {
+            input = input.strip_prefix(c)?;
+        }
+    }
+    if !input.trim().is_empty() {
+        return None;
+    }
+    if let Some(epoch) = epoch {
+        return Some(epoch);
+    }
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let days = match yday {
+        Some(yday) => days_from_civil(year, 1, 1) + yday - 1,
+        None => days_from_civil(year, month, day),
+    };
+    Some((days * 86400 + hour * 3600 + minute * 60) as f64 + second)
+}
+fn take_number(input: &mut &str, max_digits: usize) -> Option<i64> {
+    let (sign, rest) = match input.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, *input),
+    };
+    let end = rest.char_indices()
+        .take(max_digits)
+        .take_while(|&(_, c)| c.is_ascii_digit())
+        .last()
+        .map(|(i, c)| i + c.len_utf8())?;
+    let value = rest[..end].parse::<i64>().ok()?;
+    *input = &rest[end..];
+    Some(sign * value)
+}
+/// days since 1970-01-01 in proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[test]
+fn time_format_test() {
+    assert_eq!(TimeFormat::new("epoch"), TimeFormat::EpochSeconds);
+    assert_eq!(TimeFormat::new("epoch-ms").timefmt(), "%s".to_string());
+    assert_eq!(TimeFormat::new("epoch-ms").scale(), Some(1000));
+    assert_eq!(TimeFormat::new("%H:%M").timefmt(), "%H:%M".to_string());
+    assert_eq!(TimeFormat::new("%H:%M").scale(), None);
+}
+#[test]
+fn strptime_test() {
+    let iso = TimeFormat::new("%Y-%m-%dT%H:%M:%S");
+    assert_eq!(iso.parse("1970-01-01T00:00:00"), Some(0.0));
+    assert_eq!(iso.parse("2017-03-04T05:06:07.5"), Some(1488603967.5));
+    assert_eq!(iso.parse("2017-03-04"), None);
+    assert_eq!(iso.parse("2017-13-04T05:06:07"), None);
+    let human = TimeFormat::new("%d %b %Y %H:%M");
+    assert_eq!(human.parse("4 Mar 2017 05:06"), Some(1488603960.0));
+    assert_eq!(TimeFormat::new("%Y %j").parse("2000 60"), Some(951782400.0));
+    assert_eq!(TimeFormat::new("epoch-ms").parse("1500"), Some(1500.0));
+}
@@ -0,0 +1,139 @@
+use regex::Regex;
+
+/// trend computed on Rust side. derived data is written to another file for the series.
+#[derive(Debug,Clone,PartialEq)]
+pub enum Trend {
+    MovingAverage(usize),
+    Exponential(f64),
+    Loess(f64),
+}
+
+impl Trend {
+    /// parse `ma:N`, `ema:ALPHA` or `loess:SPAN`. (`none` is handled by caller)
+    pub fn new(arg: &str) -> Option<Trend> {
+        let trend_regex = Regex::new(r"^(ma|ema|loess):(\d+(\.\d+)?)$").unwrap();
+        trend_regex.captures(arg).and_then(|cap| match &cap[1] {
+            "ma" => {
+                cap[2].parse::<usize>()
+                    .ok()
+                    .and_then(|n| if n > 0 { Some(Trend::MovingAverage(n)) } else { None })
+            }
+            "ema" => {
+                cap[2].parse::<f64>()
+                    .ok()
+                    .and_then(|a| {
+                        if a > 0.0 && a <= 1.0 { Some(Trend::Exponential(a)) } else { None }
+                    })
+            }
+            "loess" => {
+                cap[2].parse::<f64>()
+                    .ok()
+                    .and_then(|f| if f > 0.0 && f <= 1.0 { Some(Trend::Loess(f)) } else { None })
+            }
+            _ => None,
+        })
+    }
+    pub fn apply(&self, xs: &[f64], ys: &[f64]) -> Vec<f64> {
+        match *self {
+            Trend::MovingAverage(window) => moving_average(ys, window),
+            Trend::Exponential(alpha) => exponential_moving_average(ys, alpha),
+            Trend::Loess(span) => loess(xs, ys, span),
+        }
+    }
+}
+
+/// trailing average. first `window - 1` points are averaged over available points.
+fn moving_average(ys: &[f64], window: usize) -> Vec<f64> {
+    let mut sum = 0.0;
+    ys.iter()
+        .enumerate()
+        .map(|(i, &y)| {
+            sum += y;
+            if i >= window {
+                sum -= ys[i - window];
+            }
+            sum / (i + 1).min(window) as f64
+        })
+        .collect()
+}
+fn exponential_moving_average(ys: &[f64], alpha: f64) -> Vec<f64> {
+    let mut state: Option<f64> = None;
+    ys.iter()
+        .map(|&y| {
+            let s = state.map(|s| alpha * y + (1.0 - alpha) * s).unwrap_or(y);
+            state = Some(s);
+            s
+        })
+        .collect()
+}
+/// locally weighted linear regression with tricube weights over `span` fraction of points.
+fn loess(xs: &[f64], ys: &[f64], span: f64) -> Vec<f64> {
+    let n = xs.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut order = (0..n).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| xs[a].partial_cmp(&xs[b]).unwrap_or(::std::cmp::Ordering::Equal));
+    let sx = order.iter().map(|&i| xs[i]).collect::<Vec<_>>();
+    let sy = order.iter().map(|&i| ys[i]).collect::<Vec<_>>();
+    let k = ((span * n as f64).ceil() as usize).max(2).min(n);
+
+    let mut fitted = vec![0.0; n];
+    let mut lo = 0;
+    for i in 0..n {
+        let x0 = sx[i];
+        // slide window [lo, lo + k) to the k nearest neighbours of x0
+        while lo + k < n && sx[lo + k] - x0 < x0 - sx[lo] {
+            lo += 1;
+        }
+        let window = lo..lo + k;
+        let max_distance = (x0 - sx[lo]).max(sx[lo + k - 1] - x0);
+        let (mut sw, mut swx, mut swy, mut swxx, mut swxy) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for j in window {
+            let w = if max_distance > 0.0 {
+                (1.0 - ((sx[j] - x0).abs() / max_distance).powi(3)).powi(3)
+            } else {
+                1.0
+            };
+            sw += w;
+            swx += w * sx[j];
+            swy += w * sy[j];
+            swxx += w * sx[j] * sx[j];
+            swxy += w * sx[j] * sy[j];
+        }
+        let denominator = sw * swxx - swx * swx;
+        fitted[order[i]] = if denominator.abs() > f64::EPSILON {
+            let slope = (sw * swxy - swx * swy) / denominator;
+            (swy - slope * swx) / sw + slope * x0
+        } else if sw > 0.0 {
+            swy / sw
+        } else {
+            sy[i]
+        };
+    }
+    fitted
+}
+
+#[test]
+fn trend_new_test() {
+    assert_eq!(Trend::new("ma:5"), Some(Trend::MovingAverage(5)));
+    assert_eq!(Trend::new("ema:0.3"), Some(Trend::Exponential(0.3)));
+    assert_eq!(Trend::new("loess:0.25"), Some(Trend::Loess(0.25)));
+    assert_eq!(Trend::new("ma:0"), None);
+    assert_eq!(Trend::new("ema:1.5"), None);
+    assert_eq!(Trend::new("ma:2.5"), None);
+    assert_eq!(Trend::new("spline:3"), None);
+}
+#[test]
+fn moving_average_test() {
+    assert_eq!(moving_average(&[1.0, 3.0, 5.0, 7.0], 2), vec![1.0, 2.0, 4.0, 6.0]);
+    assert_eq!(exponential_moving_average(&[2.0, 4.0, 4.0], 0.5),
+               vec![2.0, 3.0, 3.5]);
+}
+#[test]
+fn loess_test() {
+    let xs = (0..20).map(|x| x as f64).collect::<Vec<_>>();
+    let ys = xs.iter().map(|x| 2.0 * x + 1.0).collect::<Vec<_>>();
+    let fitted = loess(&xs, &ys, 0.3);
+    assert!(fitted.iter().zip(ys.iter()).all(|(f, y)| (f - y).abs() < 1e-9));
+}